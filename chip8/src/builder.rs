@@ -0,0 +1,188 @@
+use crate::chip::Chip8;
+use crate::memory::{Memory, MemoryError};
+use crate::platform::{ChipMode, Quirks};
+use crate::rom::Rom;
+use std::collections::HashSet;
+
+/// Builds a `Chip8` that starts in a specific state, e.g. to reproduce a
+/// bug or to set up a test scenario. Everything that isn't set is the same
+/// as after `Chip8::new`.
+///
+/// ```
+/// use chip8::builder::Chip8Builder;
+/// use chip8::platform::ChipMode;
+/// use chip8::rom::Rom;
+/// use std::collections::HashSet;
+///
+/// let quirks = HashSet::new();
+/// let chip8 = Chip8Builder::new(Rom::from_bytes([0x12, 0x00]), &ChipMode::Chip8, &quirks)
+///     .register(0x1, 0x2A)
+///     .i(0x300)
+///     .build()
+///     .unwrap();
+/// assert_eq!(chip8.register(0x1), 0x2A);
+/// assert_eq!(chip8.i(), 0x300);
+/// ```
+pub struct Chip8Builder<'a> {
+    rom: Rom,
+    mode: &'a ChipMode,
+    quirks: &'a HashSet<Quirks>,
+    ticks_per_frame: u32,
+    sleep_time: Option<u32>,
+    seed: Option<u64>,
+    load_address: u16,
+    /// The small font and its address.
+    font: ([u8; 80], u16),
+    registers: [u8; 16],
+    i: u16,
+    /// Starts at the load address when it isn't set.
+    pc: Option<u16>,
+    /// Data copied to the memory after the program is loaded.
+    memory: Vec<(u16, Vec<u8>)>,
+}
+
+impl<'a> Chip8Builder<'a> {
+    /// Instructions per frame unless `ticks_per_frame` is set, the same as
+    /// the CLI default.
+    pub const DEFAULT_TICKS_PER_FRAME: u32 = 1000;
+
+    pub fn new(rom: Rom, mode: &'a ChipMode, quirks: &'a HashSet<Quirks>) -> Self {
+        Chip8Builder {
+            rom,
+            mode,
+            quirks,
+            ticks_per_frame: Self::DEFAULT_TICKS_PER_FRAME,
+            sleep_time: None,
+            seed: None,
+            load_address: Memory::PROGRAM_ADDR_START,
+            font: (Memory::DEFAULT_FONT, 0),
+            registers: [0; 16],
+            i: 0,
+            pc: None,
+            memory: vec![],
+        }
+    }
+
+    pub fn ticks_per_frame(mut self, ticks: u32) -> Self {
+        self.ticks_per_frame = ticks;
+        self
+    }
+
+    /// Microseconds `run` sleeps per instruction, see
+    /// `Chip8::set_sleep_time`.
+    pub fn sleep_time(mut self, sleep_time: Option<u32>) -> Self {
+        self.sleep_time = sleep_time;
+        self
+    }
+
+    /// Seeds the random number generator, see `Chip8::with_seed`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Loads the program at `load_address`, see `Chip8::with_load_address`.
+    pub fn load_address(mut self, load_address: u16) -> Self {
+        self.load_address = load_address;
+        self
+    }
+
+    /// Loads the custom small `font` at `font_address`, see
+    /// `Chip8::with_font`.
+    pub fn font(mut self, font: [u8; 80], font_address: u16) -> Self {
+        self.font = (font, font_address);
+        self
+    }
+
+    /// Sets the general purpose register `Vx`, `x` is from 0x0 to 0xF.
+    pub fn register(mut self, x: u8, value: u8) -> Self {
+        assert!(x <= 0xF, "There is no register V{x:X}");
+        self.registers[x as usize] = value;
+        self
+    }
+
+    pub fn i(mut self, value: u16) -> Self {
+        self.i = value;
+        self
+    }
+
+    /// Starts the execution at `pc` instead of the load address.
+    pub fn pc(mut self, pc: u16) -> Self {
+        self.pc = Some(pc);
+        self
+    }
+
+    /// Copies `data` to the memory at `addr` on top of the loaded program,
+    /// see `Chip8::load_bytes_at`.
+    pub fn memory(mut self, addr: u16, data: impl Into<Vec<u8>>) -> Self {
+        self.memory.push((addr, data.into()));
+        self
+    }
+
+    /// Fails when the program, the font or a memory region doesn't fit
+    /// into the memory. `Chip8::reset` brings back the state after `Chip8::new`,
+    /// not the one set here.
+    pub fn build(self) -> Result<Chip8, MemoryError> {
+        let (font, font_address) = self.font;
+        let memory = Memory::with_layout(
+            self.rom.content(),
+            self.mode,
+            font,
+            font_address,
+            self.load_address,
+        )?;
+        let mut chip8 = Chip8::with_memory(
+            self.rom,
+            memory,
+            self.mode,
+            self.quirks,
+            self.ticks_per_frame,
+            self.sleep_time,
+        );
+        if let Some(seed) = self.seed {
+            chip8.set_seed(seed);
+        }
+        self.registers
+            .iter()
+            .enumerate()
+            .for_each(|(x, &value)| chip8.set_register(x as u8, value));
+        chip8.set_i(self.i);
+        if let Some(pc) = self.pc {
+            chip8.set_pc(pc);
+        }
+        self.memory
+            .iter()
+            .try_for_each(|(addr, data)| chip8.load_bytes_at(*addr, data))?;
+        Ok(chip8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_is_loaded_at_its_address() {
+        let quirks = HashSet::new();
+        let mut chip8 = Chip8Builder::new(Rom::from_bytes([0xF1, 0x29]), &ChipMode::Chip8, &quirks)
+            .font(Memory::DEFAULT_FONT, 0x050)
+            .register(0x1, 0x2)
+            .build()
+            .unwrap();
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.i(), 0x050 + 2 * 5);
+        assert_eq!(chip8.dump_memory(0x050..0x055), &Memory::DEFAULT_FONT[..5]);
+    }
+
+    #[test]
+    fn font_that_runs_into_the_program_is_an_error() {
+        let quirks = HashSet::new();
+        let result =
+            Chip8Builder::new(Rom::from_bytes([0x12, 0x00]), &ChipMode::SuperChip, &quirks)
+                .font(Memory::DEFAULT_FONT, 0x180)
+                .build();
+
+        assert!(matches!(result, Err(MemoryError::FontOutOfBounds(0x180))));
+    }
+}
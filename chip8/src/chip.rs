@@ -1,20 +1,35 @@
-use crate::display::{Display, Plane, ScreenResolution};
+use crate::debug::{RunResult, StepOutcome, StopReason, TraceEvent, WatchHit, WatchKind};
+use crate::disasm::disassemble;
+#[cfg(feature = "stats")]
+use crate::disasm::mnemonic;
+use crate::display::{Display, DrawOutcome, Plane, PlaneCollisions, ScreenResolution};
 use crate::instruction::Instruction;
 use crate::keyboard::Keyboard;
-use crate::memory::Memory;
+use crate::memory::{Memory, MemoryError};
+use crate::op::{Op, decode};
 use crate::platform::{ChipMode, Quirks};
 use crate::registers::memory::MemoryRegister;
 use crate::registers::timer::TimerRegister;
 use crate::rom::Rom;
-use crate::stack::Stack;
-use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use crate::stack::{Stack, StackError};
+use crate::state::MachineState;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeBounds;
+use std::time::{Duration, Instant};
 
-pub struct Chip8<'a> {
-    memory: Memory<'a>,
+pub struct Chip8 {
+    /// The original program, kept to reload it on reset.
+    program: Vec<u8>,
+    memory: Memory,
     stack: Stack,
-    display: Display<'a>,
+    display: Display,
     keyboard: Keyboard,
+    /// The key pressed during `Fx0A` which release is awaited.
+    awaited_key: Option<u8>,
+    /// Set while `Fx0A` waits, the keyboard keeps the presses until then.
+    waiting_for_key: bool,
     /// General purpose registers.
     registers: HashMap<u8, u8>,
     /// `I` register is generally used to store memory addresses, so only
@@ -26,35 +41,145 @@ pub struct Chip8<'a> {
     st_register: TimerRegister,
     /// PC is used to store the currently executing address.
     program_counter: u16,
+    /// Set by `00FD`, no instructions are executed after it.
+    halted: bool,
+    /// While paused frames don't execute instructions nor decrement timers.
+    paused: bool,
+    /// Set by `Dxyn` with `DisplayWait` quirk to end the current frame.
+    waiting_for_vblank: bool,
+    /// Planes the last `Dxyn` erased a pixel on.
+    plane_collisions: PlaneCollisions,
+    /// Addresses execution stops at before running the instruction.
+    breakpoints: HashSet<u16>,
+    /// Set when execution stopped at a breakpoint, so the next step runs
+    /// the instruction instead of stopping again.
+    stopped_at_breakpoint: bool,
+    /// Called before every executed instruction.
+    tracer: Option<Box<dyn FnMut(TraceEvent)>>,
+    /// Number of instructions executed since the start or the last reset.
+    instruction_count: u64,
+    /// Snapshots taken before each of the last frames, the newest is the
+    /// last one.
+    rewind_buffer: VecDeque<MachineState>,
+    /// How many snapshots `rewind_buffer` keeps, 0 disables it.
+    rewind_depth: usize,
+    /// Number of executed instructions by mnemonic.
+    #[cfg(feature = "stats")]
+    opcode_counts: HashMap<String, u64>,
 
     audio_buffer: [u8; 16],
     pitch: u16,
+    /// Source of random numbers for `Cxkk`.
+    rng: StdRng,
 
     ticks_per_frame: u32,
-    mode: &'a ChipMode,
-    quirks: &'a HashSet<Quirks>,
-    sleep_time: Option<u8>,
+    /// Multiplier of the 60 Hz rate the timers are decremented at by `run`.
+    timer_speed: f32,
+    /// Frames per second `run` is limited to, unlimited if `None`.
+    frame_rate: Option<u32>,
+    mode: ChipMode,
+    quirks: HashSet<Quirks>,
+    /// Microseconds `run` sleeps per executed instruction.
+    sleep_time: Option<u32>,
 }
 
-impl<'a> Chip8<'a> {
+#[derive(Debug)]
+pub enum Chip8Error {
+    /// The opcode at `pc` doesn't exist on `mode`. The program counter
+    /// stays at the instruction, so it can be inspected.
+    IllegalInstruction {
+        opcode: u16,
+        pc: u16,
+        mode: ChipMode,
+    },
+    /// The program counter ran off the end of the memory, so no opcode
+    /// can be fetched at `pc`.
+    ProgramCounterOutOfBounds { pc: u16 },
+    /// The opcode at `pc` is a `mode` instruction the interpreter doesn't
+    /// support yet. The program counter stays at the instruction.
+    UnimplementedInstruction {
+        opcode: u16,
+        pc: u16,
+        mode: ChipMode,
+    },
+    /// The instruction at `pc` accessed the memory it isn't allowed to.
+    /// The program counter stays at the instruction.
+    MemoryViolation { error: MemoryError, pc: u16 },
+    /// The `CALL` at `pc` nested more subroutines than the stack holds.
+    /// The program counter stays at the instruction.
+    StackOverflow { pc: u16 },
+    /// The `RET` at `pc` returned while no subroutine was called. The
+    /// program counter stays at the instruction.
+    StackUnderflow { pc: u16 },
+}
+
+impl Chip8 {
+    /// Interval between decrements of the delay and sound timers (60 Hz).
+    pub const TIMER_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    /// Where hires CHIP-8 programs continue after the `1260` prologue.
+    const HIRES_64X64_PROGRAM_START: u16 = 0x2C0;
+
+    /// Loads the program of `rom` into the memory of `mode`. Fails when the
+    /// program is empty or doesn't fit into the memory. The machine keeps
+    /// its own copy of `mode` and `quirks`.
+    ///
+    /// `Chip8Builder` sets up the rest of the machine without more
+    /// positional parameters.
     pub fn new(
         rom: Rom,
-        mode: &'a ChipMode,
-        quirks: &'a HashSet<Quirks>,
+        mode: &ChipMode,
+        quirks: &HashSet<Quirks>,
         ticks_per_frame: u32,
-        sleep_time: Option<u8>,
-    ) -> Chip8<'a> {
-        let memory = Memory::new(rom.content(), mode);
+        sleep_time: Option<u32>,
+    ) -> Result<Chip8, MemoryError> {
+        let memory = Memory::new(rom.content(), mode)?;
+        Ok(Self::with_memory(
+            rom,
+            memory,
+            mode,
+            quirks,
+            ticks_per_frame,
+            sleep_time,
+        ))
+    }
+
+    /// A machine that starts at the load address of `memory`, which has
+    /// the program of `rom` loaded.
+    pub(crate) fn with_memory(
+        rom: Rom,
+        memory: Memory,
+        mode: &ChipMode,
+        quirks: &HashSet<Quirks>,
+        ticks_per_frame: u32,
+        sleep_time: Option<u32>,
+    ) -> Chip8 {
         let memory_size = memory.get_memory_size();
+        let program_counter = memory.load_address();
         Chip8 {
+            program: rom.content().to_vec(),
             memory,
             stack: Stack::new(memory_size),
-            display: Display::new(quirks),
+            display: Display::new(mode, quirks),
             keyboard: Keyboard::default(),
+            awaited_key: None,
+            waiting_for_key: false,
             i_register: MemoryRegister::new(memory_size),
             dt_register: TimerRegister::default(),
             st_register: TimerRegister::default(),
-            program_counter: Memory::PROGRAM_ADDR_START,
+            program_counter,
+            halted: false,
+            paused: false,
+            breakpoints: HashSet::new(),
+            stopped_at_breakpoint: false,
+            tracer: None,
+            instruction_count: 0,
+            rewind_buffer: VecDeque::new(),
+            rewind_depth: 0,
+            #[cfg(feature = "stats")]
+            opcode_counts: HashMap::new(),
+            waiting_for_vblank: false,
+            plane_collisions: PlaneCollisions::default(),
             registers: {
                 let mut registers = HashMap::with_capacity(0xF);
                 registers.insert(0x0, 0);
@@ -77,112 +202,780 @@ impl<'a> Chip8<'a> {
             },
             audio_buffer: [0xFF; 16],
             pitch: 8000,
+            rng: StdRng::from_os_rng(),
+            mode: *mode,
+            quirks: quirks.clone(),
+            ticks_per_frame,
+            timer_speed: 1.0,
+            frame_rate: None,
+            sleep_time,
+        }
+    }
+
+    /// Same as `new`, but the random number generator is seeded with `seed`,
+    /// so runs with the same seed and input are reproducible.
+    pub fn with_seed(
+        rom: Rom,
+        mode: &ChipMode,
+        quirks: &HashSet<Quirks>,
+        ticks_per_frame: u32,
+        sleep_time: Option<u32>,
+        seed: u64,
+    ) -> Result<Chip8, MemoryError> {
+        Ok(Chip8 {
+            rng: StdRng::seed_from_u64(seed),
+            ..Self::new(rom, mode, quirks, ticks_per_frame, sleep_time)?
+        })
+    }
+
+    /// Same as `new`, but loads the custom small `font` (16 digits by 5
+    /// bytes) at `font_address`. `Fx29` and `Fx30` point to this font.
+    pub fn with_font(
+        rom: Rom,
+        mode: &ChipMode,
+        quirks: &HashSet<Quirks>,
+        ticks_per_frame: u32,
+        sleep_time: Option<u32>,
+        font: [u8; 80],
+        font_address: u16,
+    ) -> Result<Chip8, MemoryError> {
+        let memory = Memory::with_font(rom.content(), mode, font, font_address)?;
+        Ok(Self::with_memory(
+            rom,
+            memory,
             mode,
             quirks,
             ticks_per_frame,
             sleep_time,
+        ))
+    }
+
+    /// Same as `new`, but the program is loaded and starts at
+    /// `load_address`, e.g. `Memory::ETI_660_PROGRAM_ADDR_START`.
+    pub fn with_load_address(
+        rom: Rom,
+        mode: &ChipMode,
+        quirks: &HashSet<Quirks>,
+        ticks_per_frame: u32,
+        sleep_time: Option<u32>,
+        load_address: u16,
+    ) -> Result<Chip8, MemoryError> {
+        let memory = Memory::with_load_address(rom.content(), mode, load_address)?;
+        Ok(Self::with_memory(
+            rom,
+            memory,
+            mode,
+            quirks,
+            ticks_per_frame,
+            sleep_time,
+        ))
+    }
+
+    /// Allows `depth` nested subroutines instead of 16, for programs that
+    /// rely on a deeper stack. The subroutines called so far stay on it.
+    pub fn set_stack_depth(&mut self, depth: u8) {
+        let mut stack = Stack::with_depth(self.memory.get_memory_size(), depth);
+        stack.load_frames(self.stack.frames());
+        self.stack = stack;
+    }
+
+    /// Keeps a snapshot of the machine before each of the last `depth`
+    /// frames, so `step_back` can undo them. 0, the default, disables it and
+    /// drops the snapshots taken so far.
+    ///
+    /// Every snapshot is a full copy of the machine: about 20KB for CHIP-8
+    /// and SUPER-CHIP, 80KB for XO-CHIP and 160KB for MEGA-CHIP, mostly the
+    /// memory and the two screen planes. 600 frames, 10 seconds at 60 FPS,
+    /// take about 12MB, 48MB and 96MB respectively.
+    pub fn set_rewind_depth(&mut self, depth: usize) {
+        self.rewind_depth = depth;
+        let excess = self.rewind_buffer.len().saturating_sub(depth);
+        self.rewind_buffer.drain(..excess);
+    }
+
+    /// Undoes the last frame by restoring the snapshot taken before it, see
+    /// `set_rewind_depth`. Returns `false` when there is nothing to undo.
+    ///
+    /// Only the state `save_state` captures is restored, e.g. breakpoints
+    /// and the instruction count stay as they are.
+    pub fn step_back(&mut self) -> bool {
+        match self.rewind_buffer.pop_back() {
+            // Snapshots of this machine always fit into its memory.
+            Some(state) => self.load_state(&state).is_ok(),
+            None => false,
         }
     }
 
-    pub fn run<F>(&mut self, mut callback: F)
+    /// Reseeds the random number generator, see `with_seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Runs the program until it exits with `00FD`. `callback` is called
+    /// after each frame, even while paused, to render the frame and to
+    /// handle the input. Hitting a breakpoint or a watchpoint pauses the
+    /// machine.
+    ///
+    /// The timers are decremented at 60 Hz of the wall-clock time, no
+    /// matter how often frames are produced. The frames are paced by
+    /// `set_frame_rate` or by the callback itself, e.g. waiting for vsync.
+    ///
+    /// Stops with an error on an illegal instruction.
+    pub fn run<F>(&mut self, mut callback: F) -> Result<(), Chip8Error>
     where
-        F: FnMut(&mut Keyboard, &Display, u8, &[u8], u16),
+        F: FnMut(&mut Chip8),
     {
-        loop {
-            (0..self.ticks_per_frame).for_each(|_| {
-                self.execute();
-                if let Some(sleep_time) = self.sleep_time {
-                    std::thread::sleep(Duration::from_micros(sleep_time as u64));
-                }
-            });
+        let mut last_frame = Instant::now();
+        let mut next_frame = last_frame;
+        let mut timers_elapsed = Duration::ZERO;
+        while !self.halted {
+            if !self.paused
+                && matches!(
+                    self.execute_frame()?,
+                    StepOutcome::BreakpointHit(_) | StepOutcome::WatchpointHit(_)
+                )
+            {
+                self.pause();
+            }
+            if let Some(sleep_time) = self.sleep_time {
+                std::thread::sleep(Duration::from_micros(
+                    sleep_time as u64 * self.ticks_per_frame as u64,
+                ));
+            }
 
-            self.dt_register.tick();
-            self.st_register.tick();
+            let now = Instant::now();
+            if !self.paused {
+                timers_elapsed += (now - last_frame).mul_f32(self.timer_speed);
+            }
+            last_frame = now;
+            while timers_elapsed >= Self::TIMER_PERIOD {
+                self.dt_register.tick();
+                self.st_register.tick();
+                timers_elapsed -= Self::TIMER_PERIOD;
+            }
 
-            callback(
-                &mut self.keyboard,
-                &self.display,
-                self.st_register.get(),
-                &self.audio_buffer,
-                self.pitch,
-            );
+            callback(self);
+
+            if let Some(frame_rate) = self.frame_rate {
+                next_frame += Duration::from_secs(1) / frame_rate;
+                let now = Instant::now();
+                if next_frame > now {
+                    std::thread::sleep(next_frame - now);
+                } else {
+                    // Frames that are late aren't caught up with.
+                    next_frame = now;
+                }
+            }
         }
+        Ok(())
     }
 
-    fn execute(&mut self) {
-        let instruction = self.next_instruction();
-        match (&self.mode, instruction.nibbles()) {
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xC, n)) if n > 0 => {
-                self.scroll_n_lines_down(instruction)
+    /// Executes a single instruction. Does nothing once the machine is
+    /// halted.
+    pub fn step(&mut self) -> Result<StepOutcome, Chip8Error> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+        self.execute_unless_breakpoint()
+    }
+
+    /// Executes instructions as fast as possible until the program halts
+    /// or `max_cycles` instructions were executed, e.g. to measure the
+    /// interpreter speed. Timers are decremented once per
+    /// `ticks_per_frame` instructions, nothing sleeps.
+    ///
+    /// Breakpoints and watchpoints stop it as well.
+    pub fn run_until_halt(&mut self, max_cycles: u64) -> Result<RunResult, Chip8Error> {
+        let ticks_per_frame = self.ticks_per_frame.max(1) as u64;
+        let mut cycles = 0;
+        let reason = loop {
+            if self.halted {
+                break StopReason::Halted;
             }
-            (ChipMode::XOChip, (0, 0, 0xD, _)) => self.scroll_n_lines_up(instruction),
-            (_, (0, 0, 0xE, 0)) => self.cls(),
-            (_, (0, 0, 0xE, 0xE)) => self.ret(),
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xF, 0xB)) => {
-                self.scroll_display_4_px_right()
+            if cycles >= max_cycles {
+                break StopReason::CycleLimit;
             }
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xF, 0xC)) => {
-                self.scroll_display_4_px_left()
+            match self.execute_unless_breakpoint()? {
+                StepOutcome::BreakpointHit(addr) => break StopReason::BreakpointHit(addr),
+                StepOutcome::WatchpointHit(hit) => {
+                    cycles += 1;
+                    break StopReason::WatchpointHit(hit);
+                }
+                _ => cycles += 1,
             }
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xF, 0xD)) => self.exit_interpreter(),
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xF, 0xE)) => self.disable_hires(),
-            (ChipMode::SuperChip | ChipMode::XOChip, (0, 0, 0xF, 0xF)) => self.enable_hires(),
-            (ChipMode::Chip8, (0, _, _, _)) => self.jp_addr(instruction),
-            (_, (1, ..)) => self.jp_addr(instruction),
-            (_, (2, ..)) => self.call_addr(instruction),
-            (_, (3, ..)) => self.se_vx_byte(instruction),
-            (_, (4, ..)) => self.sne_vx_byte(instruction),
-            (ChipMode::XOChip, (5, .., 2)) => self.save_registers_range(instruction),
-            (ChipMode::XOChip, (5, .., 3)) => self.load_registers_range(instruction),
-            (_, (5, ..)) => self.se_vx_vy(instruction),
-            (_, (6, ..)) => self.ld_vx_byte(instruction),
-            (_, (7, ..)) => self.add_vx_byte(instruction),
-            (_, (8, .., 0)) => self.ld_vx_vy(instruction),
-            (_, (8, .., 1)) => self.or_vx_vy(instruction),
-            (_, (8, .., 2)) => self.and_vx_vy(instruction),
-            (_, (8, .., 3)) => self.xor_vx_vy(instruction),
-            (_, (8, .., 4)) => self.add_vx_vy(instruction),
-            (_, (8, .., 5)) => self.sub_vx_vy(instruction),
-            (_, (8, .., 6)) => self.shr_vx(instruction),
-            (_, (8, .., 7)) => self.subn_vx_vy(instruction),
-            (_, (8, .., 0xE)) => self.shl_vx(instruction),
-            (_, (9, .., 0)) => self.sne_vx_vy(instruction),
-            (_, (0xA, ..)) => self.ld_i_addr(instruction),
-            (_, (0xB, ..)) => self.jp_vo_addr(instruction),
-            (_, (0xC, ..)) => self.rnd_vx_byte(instruction),
-            (_, (0xD, ..)) => self.drw_vx_vy_n(instruction),
-            (_, (0xE, _, 0x9, 0xE)) => self.skp_vx(instruction),
-            (_, (0xE, _, 0xA, 1)) => self.sknp_vx(instruction),
-            (ChipMode::XOChip, (0xF, 0, 0, 0)) => self.load_i(),
-            (ChipMode::XOChip, (0xF, _, 0, 1)) => self.set_plane(instruction),
-            (ChipMode::XOChip, (0xF, 0, 0, 2)) => self.load_audio_buffer(),
-            (_, (0xF, _, 0, 7)) => self.ld_vx_dt(instruction),
-            (_, (0xF, _, 0, 0xA)) => self.ld_vx_k(instruction),
-            (_, (0xF, _, 1, 5)) => self.ld_dt_vx(instruction),
-            (_, (0xF, _, 1, 8)) => self.ld_st_vx(instruction),
-            (_, (0xF, _, 1, 0xE)) => self.add_i_vx(instruction),
-            (_, (0xF, _, 2, 9)) => self.ld_f_vx(instruction),
-            (ChipMode::SuperChip | ChipMode::XOChip, (0xF, _, 3, 0)) => {
-                self.load_10_byte_font_to_i(instruction)
+            if cycles % ticks_per_frame == 0 {
+                self.dt_register.tick();
+                self.st_register.tick();
             }
-            (_, (0xF, _, 3, 3)) => self.ld_b_vx(instruction),
-            (ChipMode::XOChip, (0xF, _, 3, 0xA)) => self.set_pitch(instruction),
-            (_, (0xF, _, 5, 5)) => self.ld_i_vx(instruction),
-            (_, (0xF, _, 6, 5)) => self.ld_vx_i(instruction),
-            (ChipMode::SuperChip | ChipMode::XOChip, (0xF, _, 7, 5)) => {
-                self.load_rpl_flags(instruction)
+        };
+        Ok(RunResult { cycles, reason })
+    }
+
+    /// Same as `step`, but if the next instruction is a `CALL`, runs until
+    /// the subroutine returns to the instruction after it.
+    ///
+    /// Stops early at a breakpoint, a watchpoint or once the machine is
+    /// halted, the outcome tells which one.
+    pub fn step_over(&mut self) -> Result<StepOutcome, Chip8Error> {
+        let op = self
+            .memory
+            .fetch(self.program_counter)
+            .and_then(|opcode| decode(opcode, &self.mode));
+        if !matches!(op, Some(Op::Call(_))) {
+            return self.step();
+        }
+        self.step_until_depth(self.stack.depth())
+    }
+
+    /// Runs until the current subroutine returns to its caller. Outside of
+    /// a subroutine it is the same as `step`.
+    ///
+    /// Stops early the same way as `step_over`.
+    pub fn step_out(&mut self) -> Result<StepOutcome, Chip8Error> {
+        match self.stack.depth() {
+            0 => self.step(),
+            depth => self.step_until_depth(depth - 1),
+        }
+    }
+
+    /// Executes `ticks_per_frame` instructions and decrements the timers
+    /// once, without sleeping. Stops as soon as the machine is halted and
+    /// does nothing while paused.
+    ///
+    /// A frame is 1/60 of a second of the machine time, so the timers
+    /// tick exactly once per call that executed the frame, e.g. the delay
+    /// timer set to 5 reads 0 after five calls, no matter how long they
+    /// took.
+    ///
+    /// With `DisplayWait` quirk the frame ends early after a sprite is
+    /// drawn. The frame also ends at a breakpoint or right after an
+    /// instruction hits a watchpoint.
+    pub fn step_frame(&mut self) -> Result<StepOutcome, Chip8Error> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+        if self.paused {
+            return Ok(StepOutcome::Paused);
+        }
+        let outcome = self.execute_frame()?;
+        self.dt_register.tick();
+        self.st_register.tick();
+        Ok(outcome)
+    }
+
+    /// Same as `step_frame`, but `input` is called first to update the
+    /// keyboard state, e.g. to script the keys frame by frame without a
+    /// window. `input` isn't called while halted or paused.
+    pub fn step_frame_with_input(
+        &mut self,
+        input: &mut impl FnMut(&mut Keyboard),
+    ) -> Result<StepOutcome, Chip8Error> {
+        if !self.halted && !self.paused {
+            input(&mut self.keyboard);
+        }
+        self.step_frame()
+    }
+
+    pub fn ticks_per_frame(&self) -> u32 {
+        self.ticks_per_frame
+    }
+
+    /// Changes how many instructions are executed per frame. It takes
+    /// effect from the next frame, also when called from the `run`
+    /// callback.
+    pub fn set_ticks_per_frame(&mut self, ticks: u32) {
+        self.ticks_per_frame = ticks;
+    }
+
+    /// Changes how many microseconds `run` sleeps per instruction. The
+    /// sleep happens once per frame and lasts `sleep_time * ticks_per_frame`
+    /// microseconds, so with 1000 instructions per frame 1 µs already
+    /// delays each frame by 1 ms. `None` or `Some(0)` doesn't sleep.
+    pub fn set_sleep_time(&mut self, sleep_time: Option<u32>) {
+        self.sleep_time = sleep_time;
+    }
+
+    /// Limits `run` to `frame_rate` frames per second, sleeping the rest of
+    /// every frame. `None` or 0 doesn't limit it.
+    pub fn set_frame_rate(&mut self, frame_rate: Option<u32>) {
+        self.frame_rate = frame_rate.filter(|&frame_rate| frame_rate > 0);
+    }
+
+    /// Makes `run` decrement the timers `speed` times as fast as 60 Hz,
+    /// e.g. 4.0 while fast-forwarding or 0.5 in slow motion, so they keep
+    /// up with the instructions per frame.
+    pub fn set_timer_speed(&mut self, speed: f32) {
+        self.timer_speed = speed;
+    }
+
+    /// Whether the program exited with `00FD`.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Freezes the frames, single instructions can still be executed with
+    /// `step`.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Runs exactly `n` frames without sleeping, see
+    /// `step_frame_with_input`.
+    pub fn run_frames(
+        &mut self,
+        n: u32,
+        input: &mut impl FnMut(&mut Keyboard),
+    ) -> Result<(), Chip8Error> {
+        (0..n).try_for_each(|_| self.step_frame_with_input(input).map(|_| ()))
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    /// Draws `bitmap`, `width` pixels per row, at (`x`, `y`) of the screen
+    /// on `plane`, overwriting what is there. It is meant for the host,
+    /// e.g. to show a "No ROM loaded" screen, see `Display::blit`.
+    pub fn draw_overlay(
+        &mut self,
+        x: usize,
+        y: usize,
+        bitmap: &[bool],
+        width: usize,
+        plane: Plane,
+    ) {
+        self.display.blit(x, y, bitmap, width, plane);
+    }
+
+    /// Planes `Dxyn` draws on, as selected by `Fx01`.
+    pub fn current_plane(&self) -> Plane {
+        *self.display.get_current_plane()
+    }
+
+    /// Overrides the planes selected by `Fx01`.
+    pub fn set_current_plane(&mut self, plane: Plane) {
+        self.display.set_plane(plane);
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
+    pub fn keyboard_mut(&mut self) -> &mut Keyboard {
+        &mut self.keyboard
+    }
+
+    /// The 16 bytes audio pattern played while the sound timer is active.
+    pub fn audio_buffer(&self) -> &[u8; 16] {
+        &self.audio_buffer
+    }
+
+    /// Replaces the audio pattern as `F002` would.
+    pub fn set_audio_buffer(&mut self, buffer: [u8; 16]) {
+        self.audio_buffer = buffer;
+    }
+
+    /// Playback rate of the audio pattern in Hz.
+    pub fn pitch(&self) -> u16 {
+        self.pitch
+    }
+
+    pub fn set_pitch(&mut self, pitch: u16) {
+        self.pitch = pitch;
+    }
+
+    /// Brings the machine back to its initial state and reloads the
+    /// program, so it starts over. Mode and quirks stay the same.
+    pub fn reset(&mut self) {
+        self.memory.reset(&self.program);
+        self.stack.load_frames(&[]);
+        self.display.reset();
+        self.awaited_key = None;
+        self.waiting_for_key = false;
+        self.registers.values_mut().for_each(|value| *value = 0);
+        self.i_register.set(0);
+        self.dt_register.set(0);
+        self.st_register.set(0);
+        self.program_counter = self.memory.load_address();
+        self.halted = false;
+        self.stopped_at_breakpoint = false;
+        self.plane_collisions = PlaneCollisions::default();
+        self.instruction_count = 0;
+        self.rewind_buffer.clear();
+        #[cfg(feature = "stats")]
+        self.opcode_counts.clear();
+        self.audio_buffer = [0xFF; 16];
+        self.pitch = 8000;
+    }
+
+    /// Copies the memory in `range`, e.g. `..` for the whole memory: 4KB
+    /// for CHIP-8 and SUPER-CHIP and 64KB for XO-CHIP. The part of the
+    /// range past the end of the memory is left out.
+    pub fn dump_memory(&self, range: impl RangeBounds<u16>) -> Vec<u8> {
+        self.memory.dump(range)
+    }
+
+    /// Writes `data` to the memory at `addr`, e.g. to preload a data file
+    /// into the high memory before running.
+    ///
+    /// Unlike writes made by the program, it bypasses the protection of
+    /// the interpreter area 0x000-0x1FF, so the font can be replaced too.
+    /// Watchpoints aren't triggered. The data is lost on `reset`.
+    pub fn load_bytes_at(&mut self, addr: u16, data: &[u8]) -> Result<(), MemoryError> {
+        self.memory.load_bytes_at(addr, data)
+    }
+
+    /// Number of instructions executed since the start or the last reset.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Number of executed instructions by mnemonic, e.g. `DRW` or `LD`.
+    #[cfg(feature = "stats")]
+    pub fn opcode_counts(&self) -> &HashMap<String, u64> {
+        &self.opcode_counts
+    }
+
+    /// Value of the general purpose register `Vx`.
+    pub fn register(&self, x: u8) -> u8 {
+        self.registers[&x]
+    }
+
+    /// Sets the general purpose register `Vx`, `x` is from 0x0 to 0xF.
+    pub fn set_register(&mut self, x: u8, value: u8) {
+        assert!(x <= 0xF, "There is no register V{x:X}");
+        self.registers.insert(x, value);
+    }
+
+    pub fn i(&self) -> u16 {
+        self.i_register.get()
+    }
+
+    pub fn set_i(&mut self, value: u16) {
+        self.i_register.set(value);
+    }
+
+    /// Return addresses of the called subroutines, the innermost is the
+    /// last.
+    pub fn call_stack(&self) -> &[u16] {
+        self.stack.frames()
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// Moves the program counter to `pc`, wrapped into the memory.
+    pub fn set_pc(&mut self, pc: u16) {
+        self.program_counter = pc & self.memory.get_memory_size();
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.dt_register.get()
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.st_register.get()
+    }
+
+    /// Sets the delay timer as `Fx15` does.
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.dt_register.set(value);
+    }
+
+    /// Sets the sound timer as `Fx18` does.
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.st_register.set(value);
+    }
+
+    /// Planes the last `Dxyn` erased a pixel on. VF only tells whether it
+    /// happened on any of them, this helps to debug overlapping graphics.
+    pub fn plane_collisions(&self) -> PlaneCollisions {
+        self.plane_collisions
+    }
+
+    /// Whether the sound is playing, i.e. the sound timer is not zero.
+    pub fn is_beeping(&self) -> bool {
+        self.st_register.get() > 0
+    }
+
+    /// Takes a snapshot of the whole machine.
+    pub fn save_state(&self) -> MachineState {
+        let (first_plane, second_plane) = self.display.planes();
+        MachineState {
+            memory: self.memory.map().to_vec(),
+            rpl_flags: self.memory.read_rpl_flags().to_vec(),
+            registers: std::array::from_fn(|register| self.registers[&(register as u8)]),
+            i_register: self.i_register.get(),
+            program_counter: self.program_counter,
+            halted: self.halted,
+            stack: self.stack.frames().to_vec(),
+            delay_timer: self.dt_register.get(),
+            sound_timer: self.st_register.get(),
+            first_plane: first_plane.to_vec(),
+            second_plane: second_plane.to_vec(),
+            current_plane: *self.display.get_current_plane(),
+            is_hires: self.display.is_hires(),
+            is_hires_64x64: self.display.is_hires_64x64(),
+            is_mega: self.display.is_mega(),
+            audio_buffer: self.audio_buffer,
+            pitch: self.pitch,
+            awaited_key: self.awaited_key,
+        }
+    }
+
+    /// The machine state as a single JSON object for external debuggers:
+    /// `version`, `pc`, `i`, `registers` (V0 to VF), `stack`, the timers,
+    /// the `halted`, `hires`, `hires_64x64` and `mega` flags, the selected
+    /// `plane`, `awaited_key`, `pitch`, `rpl_flags` and `memory` encoded as
+    /// base64.
+    ///
+    /// The schema only changes together with `STATE_JSON_VERSION`.
+    #[cfg(feature = "serde")]
+    pub fn state_json(&self) -> String {
+        self.save_state().to_json()
+    }
+
+    /// Restores the machine from a snapshot taken with `save_state`, so the
+    /// execution continues exactly from where the snapshot was taken.
+    ///
+    /// Fails without changing anything when the memory of the snapshot is
+    /// larger, e.g. it was taken on XO-CHIP and loaded on CHIP-8.
+    pub fn load_state(&mut self, state: &MachineState) -> Result<(), MemoryError> {
+        self.memory.load_map(&state.memory)?;
+        self.memory.write_rpl_flags(&state.rpl_flags);
+        state
+            .registers
+            .iter()
+            .enumerate()
+            .for_each(|(register, &value)| {
+                self.registers.insert(register as u8, value);
+            });
+        self.i_register.set(state.i_register);
+        self.program_counter = state.program_counter;
+        self.halted = state.halted;
+        self.stack.load_frames(&state.stack);
+        self.dt_register.set(state.delay_timer);
+        self.st_register.set(state.sound_timer);
+        self.display.load_planes(
+            &state.first_plane,
+            &state.second_plane,
+            state.is_hires,
+            state.is_hires_64x64,
+            state.is_mega,
+        );
+        self.display.set_plane(state.current_plane);
+        self.audio_buffer = state.audio_buffer;
+        self.pitch = state.pitch;
+        self.awaited_key = state.awaited_key;
+        Ok(())
+    }
+
+    /// Breaks execution whenever `addr` is accessed the way `kind`
+    /// describes. The hit is reported by `step` and `step_frame` after the
+    /// instruction is executed.
+    pub fn add_watchpoint(&mut self, addr: u16, kind: WatchKind) {
+        self.memory.add_watchpoint(addr, kind);
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: u16) {
+        self.memory.remove_watchpoint(addr);
+    }
+
+    /// Stops execution before the instruction at `addr` is executed.
+    pub fn set_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn clear_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Installs `tracer` which is called with every instruction right
+    /// before it is executed, e.g. to log the whole run into a file.
+    pub fn set_tracer(&mut self, tracer: Box<dyn FnMut(TraceEvent)>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn remove_tracer(&mut self) {
+        self.tracer = None;
+    }
+
+    /// Executes up to `ticks_per_frame` instructions.
+    fn execute_frame(&mut self) -> Result<StepOutcome, Chip8Error> {
+        if self.rewind_depth > 0 {
+            if self.rewind_buffer.len() >= self.rewind_depth {
+                self.rewind_buffer.pop_front();
             }
-            (ChipMode::SuperChip | ChipMode::XOChip, (0xF, _, 8, 5)) => {
-                self.read_rpl_flags(instruction)
+            self.rewind_buffer.push_back(self.save_state());
+        }
+        self.waiting_for_vblank = false;
+        let mut outcome = StepOutcome::Executed;
+        for _ in 0..self.ticks_per_frame {
+            outcome = self.execute_unless_breakpoint()?;
+            if outcome != StepOutcome::Executed || self.halted || self.waiting_for_vblank {
+                break;
             }
-            _ => {
-                panic!(
-                    "Unknown instruction 0x{:04X} for {}",
-                    instruction.value(),
-                    self.mode,
-                )
+        }
+        // Presses are only kept across frames while `Fx0A` waits for them.
+        if !self.waiting_for_key {
+            self.keyboard.clear_presses();
+        }
+        Ok(outcome)
+    }
+
+    /// Steps at least once and until the stack is at most `depth` frames
+    /// deep.
+    fn step_until_depth(&mut self, depth: u8) -> Result<StepOutcome, Chip8Error> {
+        loop {
+            let outcome = self.step()?;
+            if outcome != StepOutcome::Executed || self.stack.depth() <= depth {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    fn execute_unless_breakpoint(&mut self) -> Result<StepOutcome, Chip8Error> {
+        if !self.stopped_at_breakpoint && self.breakpoints.contains(&self.program_counter) {
+            self.stopped_at_breakpoint = true;
+            return Ok(StepOutcome::BreakpointHit(self.program_counter));
+        }
+        self.stopped_at_breakpoint = false;
+        Ok(match self.execute()? {
+            Some(hit) => StepOutcome::WatchpointHit(hit),
+            None => StepOutcome::Executed,
+        })
+    }
+
+    fn execute(&mut self) -> Result<Option<WatchHit>, Chip8Error> {
+        let pc = self.program_counter;
+        let instruction = self.next_instruction()?;
+        let Some(op) = decode(instruction.value(), &self.mode) else {
+            self.program_counter = pc;
+            return Err(Chip8Error::IllegalInstruction {
+                opcode: instruction.value(),
+                pc,
+                mode: self.mode,
+            });
+        };
+        if op.is_unimplemented() {
+            self.program_counter = pc;
+            return Err(Chip8Error::UnimplementedInstruction {
+                opcode: instruction.value(),
+                pc,
+                mode: self.mode,
+            });
+        }
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer(TraceEvent {
+                pc,
+                opcode: instruction.value(),
+                mnemonic: disassemble(Instruction::new(instruction.value()), &self.mode),
+                registers: std::array::from_fn(|register| self.registers[&(register as u8)]),
+                i: self.i_register.get(),
+            });
+        }
+        self.instruction_count += 1;
+        #[cfg(feature = "stats")]
+        self.count_opcode(&instruction);
+        match op {
+            Op::Scd(_) => self.scroll_n_lines_down(instruction),
+            Op::Scu(_) => self.scroll_n_lines_up(instruction),
+            Op::Cls => self.cls(),
+            Op::Ret => self
+                .ret()
+                .map_err(|error| self.stack_violation(error, pc))?,
+            Op::Scr => self.scroll_display_4_px_right(),
+            Op::Scl => self.scroll_display_4_px_left(),
+            Op::Exit => self.exit_interpreter(),
+            Op::Low => self.disable_hires(),
+            Op::High => self.enable_hires(),
+            Op::MegaOff => self.disable_mega(),
+            Op::MegaOn => self.enable_mega(),
+            Op::Sys(0x230) if self.display.is_hires_64x64() => self.cls(),
+            Op::Sys(_) => self.jp_addr(instruction),
+            Op::Jp(0x260) if self.mode == ChipMode::Chip8 && pc == Memory::PROGRAM_ADDR_START => {
+                self.enable_hires_64x64()
             }
+            Op::Jp(_) => self.jp_addr(instruction),
+            Op::Call(_) => self
+                .call_addr(instruction)
+                .map_err(|error| self.stack_violation(error, pc))?,
+            Op::SeByte { .. } => self.se_vx_byte(instruction),
+            Op::SneByte { .. } => self.sne_vx_byte(instruction),
+            Op::Save { .. } => self
+                .save_registers_range(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::Load { .. } => self
+                .load_registers_range(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::SeReg { .. } => self.se_vx_vy(instruction),
+            Op::LdByte { .. } => self.ld_vx_byte(instruction),
+            Op::AddByte { .. } => self.add_vx_byte(instruction),
+            Op::LdReg { .. } => self.ld_vx_vy(instruction),
+            Op::Or { .. } => self.or_vx_vy(instruction),
+            Op::And { .. } => self.and_vx_vy(instruction),
+            Op::Xor { .. } => self.xor_vx_vy(instruction),
+            Op::AddReg { .. } => self.add_vx_vy(instruction),
+            Op::Sub { .. } => self.sub_vx_vy(instruction),
+            Op::Shr { .. } => self.shr_vx(instruction),
+            Op::Subn { .. } => self.subn_vx_vy(instruction),
+            Op::Shl { .. } => self.shl_vx(instruction),
+            Op::SneReg { .. } => self.sne_vx_vy(instruction),
+            Op::LdI(_) => self.ld_i_addr(instruction),
+            Op::JpV0(_) => self.jp_vo_addr(instruction),
+            Op::Rnd { .. } => self.rnd_vx_byte(instruction),
+            Op::Drw { .. } => self
+                .drw_vx_vy_n(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::Skp(_) => self.skp_vx(instruction),
+            Op::Sknp(_) => self.sknp_vx(instruction),
+            Op::LdILong => self.load_i()?,
+            Op::Plane(_) => self.set_plane(instruction),
+            Op::Audio => self
+                .load_audio_buffer()
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::LdVxDt(_) => self.ld_vx_dt(instruction),
+            Op::LdVxK(_) => self.ld_vx_k(instruction),
+            Op::LdDtVx(_) => self.ld_dt_vx(instruction),
+            Op::LdStVx(_) => self.ld_st_vx(instruction),
+            Op::AddIVx(_) => self.add_i_vx(instruction),
+            Op::LdFVx(_) => self.ld_f_vx(instruction),
+            Op::LdHfVx(_) => self.load_10_byte_font_to_i(instruction),
+            Op::LdBVx(_) => self
+                .ld_b_vx(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::Pitch(_) => self.set_pitch_vx(instruction),
+            Op::LdIVx(_) => self
+                .ld_i_vx(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::LdVxI(_) => self
+                .ld_vx_i(instruction)
+                .map_err(|error| self.memory_violation(error, pc))?,
+            Op::LdRVx(_) => self.load_rpl_flags(instruction),
+            Op::LdVxR(_) => self.read_rpl_flags(instruction),
+            Op::LdIHi(_)
+            | Op::LdPal(_)
+            | Op::SprW(_)
+            | Op::SprH(_)
+            | Op::Alpha(_)
+            | Op::DigiSnd(_)
+            | Op::StopSnd
+            | Op::BMode(_)
+            | Op::CCol(_) => unreachable!("Unimplemented instructions are rejected above"),
         }
+        Ok(self
+            .memory
+            .take_watch_hit()
+            .map(|(addr, kind)| WatchHit { addr, kind, pc }))
     }
 
     /// 00CN - Scroll display N lines down
@@ -197,8 +990,14 @@ impl<'a> Chip8<'a> {
 
     /// 00E0 - CLS
     /// Clear the display.
+    ///
+    /// XO-CHIP clears only the selected planes, the other platforms clear
+    /// the whole screen.
     fn cls(&mut self) {
-        self.display.clear();
+        match self.mode {
+            ChipMode::XOChip => self.display.clear(),
+            _ => self.display.clear_all(),
+        }
     }
 
     /// 00EE - RET
@@ -206,8 +1005,9 @@ impl<'a> Chip8<'a> {
     ///
     /// The interpreter sets the program counter to the address at the top of the stack,
     /// then subtracts 1 from the stack pointer.
-    fn ret(&mut self) {
-        self.program_counter = self.stack.pull();
+    fn ret(&mut self) -> Result<(), StackError> {
+        self.program_counter = self.stack.pull()?;
+        Ok(())
     }
 
     /// 00FB - Scroll display 4 pixels right
@@ -221,8 +1021,8 @@ impl<'a> Chip8<'a> {
     }
 
     /// 00FD - Exit interpreter
-    fn exit_interpreter(&self) {
-        std::process::exit(0);
+    fn exit_interpreter(&mut self) {
+        self.halted = true;
     }
 
     /// 00FE - Disable high resolution screen mode for full-screen graphics.
@@ -235,6 +1035,27 @@ impl<'a> Chip8<'a> {
         self.display.enable_hires();
     }
 
+    /// 0010 - Disable the MEGA-CHIP mode, back to the SUPER-CHIP screen.
+    fn disable_mega(&mut self) {
+        self.display.disable_mega();
+    }
+
+    /// 0011 - Enable the MEGA-CHIP mode with the 256x192 screen.
+    fn enable_mega(&mut self) {
+        self.display.enable_mega();
+    }
+
+    /// 1260 at 0x200 - Start the hires CHIP-8 program.
+    ///
+    /// Hires CHIP-8 programs start with a jump to 0x260, where the original
+    /// interpreter had a patch switching the COSMAC VIP to the 64x64 mode.
+    /// The program itself starts at 0x2C0. In this mode `0230` clears the
+    /// screen.
+    fn enable_hires_64x64(&mut self) {
+        self.display.enable_hires_64x64();
+        self.program_counter = Self::HIRES_64X64_PROGRAM_START;
+    }
+
     /// 1nnn - JP addr
     /// Jump to location nnn.
     ///
@@ -248,9 +1069,10 @@ impl<'a> Chip8<'a> {
     ///
     /// The interpreter increments the stack pointer, then puts the current PC on the
     /// top of the stack. The PC is then set to nnn.
-    fn call_addr(&mut self, instruction: Instruction) {
-        self.stack.push(self.program_counter);
+    fn call_addr(&mut self, instruction: Instruction) -> Result<(), StackError> {
+        self.stack.push(self.program_counter)?;
         self.program_counter = instruction.nnn();
+        Ok(())
     }
 
     /// 3xkk - SE Vx, byte
@@ -278,29 +1100,30 @@ impl<'a> Chip8<'a> {
     }
 
     /// 0x5XY2 - Save an inclusive range of registers vx - vy to memory starting at `I`.
-    fn save_registers_range(&mut self, instruction: Instruction) {
+    fn save_registers_range(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
         let range = if instruction.x() > instruction.y() {
             Box::new((instruction.y()..=instruction.x()).rev()) as Box<dyn Iterator<Item = _>>
         } else {
-            Box::new((instruction.x()..=instruction.y()).into_iter()) as Box<dyn Iterator<Item = _>>
+            Box::new(instruction.x()..=instruction.y()) as Box<dyn Iterator<Item = _>>
         };
-        range.enumerate().for_each(|(i, register)| {
+        range.enumerate().try_for_each(|(i, register)| {
             self.memory
-                .write(self.i_register.add(i as u16), self.registers[&register]);
-        });
+                .write(self.i_register.add(i as u16), self.registers[&register])
+        })
     }
 
     /// 0x5XY3 - Load an inclusive range of registers vx - vy from memory starting at `I`.
-    fn load_registers_range(&mut self, instruction: Instruction) {
+    fn load_registers_range(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
         let range = if instruction.x() > instruction.y() {
             Box::new((instruction.y()..=instruction.x()).rev()) as Box<dyn Iterator<Item = _>>
         } else {
-            Box::new((instruction.x()..=instruction.y()).into_iter()) as Box<dyn Iterator<Item = _>>
+            Box::new(instruction.x()..=instruction.y()) as Box<dyn Iterator<Item = _>>
         };
-        range.enumerate().for_each(|(i, register)| {
+        range.enumerate().try_for_each(|(i, register)| {
             self.registers
-                .insert(register, self.memory.read(self.i_register.add(i as u16)));
-        });
+                .insert(register, self.memory.read(self.i_register.add(i as u16))?);
+            Ok(())
+        })
     }
 
     /// 5xy0 - SE Vx, Vy
@@ -424,6 +1247,9 @@ impl<'a> Chip8<'a> {
     ///
     /// If the least-significant bit of Vx is 1, then VF is set to 1, otherwise 0. Then
     /// Vx is divided by 2.
+    ///
+    /// Without `ShiftIgnoreVY` quirk Vy is shifted instead, as on the COSMAC VIP. The
+    /// result still goes to Vx and Vy is left as it is.
     fn shr_vx(&mut self, instruction: Instruction) {
         let target_register = if self.quirks.contains(&Quirks::ShiftIgnoreVY) {
             instruction.x()
@@ -453,6 +1279,8 @@ impl<'a> Chip8<'a> {
     ///
     /// If the most-significant bit of Vx is 1, then VF is set to 1, otherwise to 0.
     /// Then Vx is multiplied by 2.
+    ///
+    /// Without `ShiftIgnoreVY` quirk Vy is shifted instead, see `shr_vx`.
     fn shl_vx(&mut self, instruction: Instruction) {
         let target_register = if self.quirks.contains(&Quirks::ShiftIgnoreVY) {
             instruction.x()
@@ -503,12 +1331,17 @@ impl<'a> Chip8<'a> {
     /// Jump to address XNN + vX
     ///
     /// The program counter is set to xnn plus the value of Vx.
+    ///
+    /// SUPER-CHIP jumps with Vx, CHIP-8 and XO-CHIP (as Octo does) with V0.
+    /// `JumpWithV0` and `JumpWithX` quirks override it, in that order.
     fn jp_vo_addr(&mut self, instruction: Instruction) {
-        let target_register = if self.quirks.contains(&Quirks::JumpWithX) {
-            instruction.x()
+        let jumps_with_x = if self.quirks.contains(&Quirks::JumpWithV0) {
+            false
         } else {
-            0
+            self.quirks.contains(&Quirks::JumpWithX)
+                || matches!(self.mode, ChipMode::SuperChip | ChipMode::MegaChip)
         };
+        let target_register = if jumps_with_x { instruction.x() } else { 0 };
         let register_value = self.registers[&target_register];
         self.program_counter = instruction.nnn() + register_value as u16;
     }
@@ -520,7 +1353,7 @@ impl<'a> Chip8<'a> {
     /// ANDed with the value kk. The results are stored in Vx.
     fn rnd_vx_byte(&mut self, instruction: Instruction) {
         self.registers
-            .insert(instruction.x(), rand::random::<u8>() & instruction.kk());
+            .insert(instruction.x(), self.rng.random::<u8>() & instruction.kk());
     }
 
     /// *CHIP-8*
@@ -528,7 +1361,11 @@ impl<'a> Chip8<'a> {
     /// Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
     ///
     /// *SCHIP*
-    /// If N=0 and hires mode, show 16x16 sprite.
+    /// If N=0 and hires mode, show 16x16 sprite. In hires mode VF is set to
+    /// the number of rows that erased a pixel plus the number of rows clipped
+    /// at the bottom of the screen.
+    ///
+    /// On CHIP-8 `Dxy0` draws nothing and clears VF.
     ///
     /// The interpreter reads n bytes from memory, starting at the address stored
     /// in I. These bytes are then displayed as sprites on screen at coordinates (Vx, Vy).
@@ -536,70 +1373,67 @@ impl<'a> Chip8<'a> {
     /// be erased, VF is set to 1, otherwise it is set to 0. If the sprite is positioned
     /// so part of it is outside the coordinates of the display, it wraps around to
     /// the opposite side of the screen.
-    fn drw_vx_vy_n(&mut self, instruction: Instruction) {
-        let pixel_erased = match (self.mode, instruction.n()) {
+    fn drw_vx_vy_n(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
+        let mut collisions = PlaneCollisions::default();
+        let outcome = match (self.mode, instruction.n()) {
             (_, n) if n != 0 => {
-                let sprites_to_draw = match self.display.get_current_plane() {
-                    Plane::First | Plane::Second => vec![(
-                        *self.display.get_current_plane(),
-                        self.memory.read_n_bytes(self.i_register.get(), n as u16),
-                    )],
-                    Plane::Both => vec![
-                        (
-                            Plane::First,
-                            self.memory.read_n_bytes(self.i_register.get(), n as u16),
-                        ),
-                        (
-                            Plane::Second,
-                            self.memory
-                                .read_n_bytes(self.i_register.add(n as u16), n as u16),
-                        ),
-                    ],
-                };
-                sprites_to_draw
-                    .into_iter()
-                    .map(|(plane, sprite)| {
-                        self.display.draw_sprite(
-                            self.registers[&instruction.x()] as usize,
-                            self.registers[&instruction.y()] as usize,
-                            &sprite,
-                            plane,
-                        )
+                let plane = *self.display.get_current_plane();
+                let sprites = (0..plane.planes().len() as u16)
+                    .map(|index| {
+                        self.memory
+                            .read_n_bytes(self.i_register.add(index * n as u16), n as u16)
                     })
-                    .fold(false, |acc, is_pixel_erased| acc || is_pixel_erased)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .concat();
+                let (outcome, plane_collisions) = self.display.draw_sprite_detailed(
+                    self.registers[&instruction.x()] as usize,
+                    self.registers[&instruction.y()] as usize,
+                    &sprites,
+                    plane,
+                );
+                collisions = plane_collisions;
+                outcome
             }
-            (ChipMode::SuperChip | ChipMode::XOChip, 0) => {
+            (ChipMode::Chip8, _) => DrawOutcome::default(),
+            (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, _) => {
                 let sprites_to_draw = match self.display.get_current_plane() {
                     Plane::First | Plane::Second => vec![(
                         *self.display.get_current_plane(),
-                        self.memory.read_n_2bytes(self.i_register.get(), 16),
+                        self.memory.read_n_2bytes(self.i_register.get(), 16)?,
                     )],
                     Plane::Both => vec![
                         (
                             Plane::First,
-                            self.memory.read_n_2bytes(self.i_register.get(), 16),
+                            self.memory.read_n_2bytes(self.i_register.get(), 16)?,
                         ),
                         (
                             Plane::Second,
-                            self.memory.read_n_2bytes(self.i_register.add(32), 16),
+                            self.memory.read_n_2bytes(self.i_register.add(32), 16)?,
                         ),
                     ],
                 };
                 sprites_to_draw
                     .into_iter()
                     .map(|(plane, sprite)| {
-                        self.display.draw_16_16_sprite(
+                        let outcome = self.display.draw_16_16_sprite(
                             self.registers[&instruction.x()] as usize,
                             self.registers[&instruction.y()] as usize,
                             sprite.try_into().unwrap(),
                             plane,
-                        )
+                        );
+                        collisions.record(plane, outcome);
+                        outcome
                     })
-                    .fold(false, |acc, is_pixel_erased| acc || is_pixel_erased)
+                    .fold(DrawOutcome::default(), DrawOutcome::merge)
             }
-            _ => panic!("Unable to draw sprite.",),
         };
-        self.registers.insert(0xF, pixel_erased as u8);
+        self.plane_collisions = collisions;
+        let flag = self.display.collision_flag(outcome);
+        self.registers.insert(0xF, flag);
+        if self.quirks.contains(&Quirks::DisplayWait) {
+            self.waiting_for_vblank = true;
+        }
+        Ok(())
     }
 
     /// Ex9E - SKP Vx
@@ -627,9 +1461,10 @@ impl<'a> Chip8<'a> {
     }
 
     /// 0xF000 0xNNNN - Load `I` with a 16-bit address.
-    fn load_i(&mut self) {
-        let new_i_value = self.next_instruction().value();
+    fn load_i(&mut self) -> Result<(), Chip8Error> {
+        let new_i_value = self.next_instruction()?.value();
         self.i_register.set(new_i_value);
+        Ok(())
     }
 
     /// 0xFX01 - Select zero or more drawing planes by bitmask (0 <= X <= 3).
@@ -639,19 +1474,20 @@ impl<'a> Chip8<'a> {
             1 => Plane::First,
             2 => Plane::Second,
             3 => Plane::Both,
-            invalid_plane => panic!("Invalid plane to select {invalid_plane}."),
+            _ => unreachable!("Planes above 3 are rejected by decode"),
         };
         self.display.set_plane(plane);
     }
 
     /// 0xF002 - Store 16 bytes starting at `I` in the audio pattern buffer.
-    fn load_audio_buffer(&mut self) {
+    fn load_audio_buffer(&mut self) -> Result<(), MemoryError> {
         let buffer: [u8; 16] = self
             .memory
-            .read_n_bytes(self.i_register.get(), 16)
+            .read_n_bytes(self.i_register.get(), 16)?
             .try_into()
             .unwrap();
         self.audio_buffer = buffer;
+        Ok(())
     }
 
     /// Fx07 - LD Vx, DT
@@ -668,12 +1504,44 @@ impl<'a> Chip8<'a> {
     ///
     /// All execution stops until a key is pressed, then the value of that key is
     /// stored in Vx.
+    ///
+    /// With `WaitForKeyRelease` quirk the key is stored only after it is
+    /// released.
+    ///
+    /// A key pressed and released within the frame or while waiting still
+    /// counts as pressed.
     fn ld_vx_k(&mut self, instruction: Instruction) {
-        if let Some(pressed_key) = self.keyboard.pressed_key() {
-            self.registers.insert(instruction.x(), pressed_key);
-        } else {
-            self.program_counter -= 2;
-        };
+        self.waiting_for_key = true;
+        if !self.quirks.contains(&Quirks::WaitForKeyRelease) {
+            if let Some(pressed_key) = self.take_key_press() {
+                self.registers.insert(instruction.x(), pressed_key);
+                self.waiting_for_key = false;
+            } else {
+                self.rewind_program_counter();
+            };
+            return;
+        }
+
+        match self.awaited_key {
+            Some(awaited_key) if !self.keyboard.is_key_pressed(awaited_key) => {
+                self.registers.insert(instruction.x(), awaited_key);
+                self.awaited_key = None;
+                self.waiting_for_key = false;
+            }
+            Some(_) => self.rewind_program_counter(),
+            None => {
+                self.awaited_key = self.take_key_press();
+                self.rewind_program_counter();
+            }
+        }
+    }
+
+    /// A key pressed since `Fx0A` started waiting, or else a key that is
+    /// held down.
+    fn take_key_press(&mut self) -> Option<u8> {
+        self.keyboard
+            .take_press()
+            .or_else(|| self.keyboard.pressed_key())
     }
 
     /// Fx15 - LD DT, Vx
@@ -700,7 +1568,11 @@ impl<'a> Chip8<'a> {
     /// The values of I and Vx are added, and the results are stored in `I`.
     fn add_i_vx(&mut self, instruction: Instruction) {
         let register_x = self.registers[&instruction.x()];
+        let is_overflow = self.i_register.overflows(register_x as u16);
         self.i_register.set(self.i_register.add(register_x as u16));
+        if self.quirks.contains(&Quirks::AddIOverflowSetsVF) {
+            self.registers.insert(0xF, is_overflow as u8);
+        }
     }
 
     /// Fx29 - LD F, Vx
@@ -709,6 +1581,8 @@ impl<'a> Chip8<'a> {
     /// The value of I is set to the location for the hexadecimal sprite corresponding
     /// to the value of Vx. See section 2.4, Display, for more information on the
     /// Chip-8 hexadecimal font.
+    ///
+    /// Only the low nibble of Vx is used, as the original interpreter did.
     fn ld_f_vx(&mut self, instruction: Instruction) {
         let register_x = self.registers[&instruction.x()];
         self.i_register.set(
@@ -718,6 +1592,9 @@ impl<'a> Chip8<'a> {
     }
 
     /// Fx30 - Point I to 10-byte font sprite for digit VX (0..F)
+    ///
+    /// Both SUPER-CHIP and XO-CHIP have the large font for all 16 digits.
+    /// Only the low nibble of Vx is used, like for `Fx29`.
     fn load_10_byte_font_to_i(&mut self, instruction: Instruction) {
         let register_x = self.registers[&instruction.x()];
         self.i_register.set(
@@ -732,17 +1609,22 @@ impl<'a> Chip8<'a> {
     /// The interpreter takes the decimal value of Vx, and places the hundreds digit
     /// in memory at location in I, the tens digit at location I+1, and the ones
     /// digit at location I+2.
-    fn ld_b_vx(&mut self, instruction: Instruction) {
+    fn ld_b_vx(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
         let register_x = self.registers[&instruction.x()];
-        self.memory.write(self.i_register.get(), register_x / 100);
+        self.memory.write(self.i_register.get(), register_x / 100)?;
         self.memory
-            .write(self.i_register.add(1), (register_x / 10) % 10);
-        self.memory.write(self.i_register.add(2), register_x % 10);
+            .write(self.i_register.add(1), (register_x / 10) % 10)?;
+        self.memory.write(self.i_register.add(2), register_x % 10)
     }
 
     /// 0xFx3A - Set the audio pattern playback rate to 4000 * 2 ^ ((Vx - 64) / 48) Hz.
-    fn set_pitch(&mut self, instruction: Instruction) {
-        self.pitch = 4000 * 2u16.pow((self.registers[&instruction.x()] as u32 - 64) / 48);
+    ///
+    /// `Vx` is used as a signed offset from 64, so the pitch ranges from
+    /// ~1587 Hz (`Vx` = 0) to ~63 kHz (`Vx` = 255).
+    fn set_pitch_vx(&mut self, instruction: Instruction) {
+        let register_x = self.registers[&instruction.x()] as f64;
+        let pitch = 4000.0 * 2f64.powf((register_x - 64.0) / 48.0);
+        self.pitch = pitch.round().clamp(0.0, u16::MAX as f64) as u16;
     }
 
     /// Fx55 - LD [I], Vx
@@ -750,17 +1632,21 @@ impl<'a> Chip8<'a> {
     ///
     /// The interpreter copies the values of registers V0 through Vx into memory,
     /// starting at the address in `I`.
-    fn ld_i_vx(&mut self, instruction: Instruction) {
-        (0..=instruction.x()).for_each(|register| {
+    ///
+    /// Addresses past the end of the memory wrap around to its start, where
+    /// the interpreter area makes the write fail. `I` wraps the same way.
+    fn ld_i_vx(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
+        (0..=instruction.x()).try_for_each(|register| {
             self.memory.write(
                 self.i_register.add(register as u16),
                 *self.registers.get(&register).unwrap(),
-            );
-        });
+            )
+        })?;
         if self.quirks.contains(&Quirks::IRegisterIncrementedWithX) {
             self.i_register
-                .set(self.i_register.get() + instruction.x() as u16 + 1);
+                .set(self.i_register.add(instruction.x() as u16 + 1));
         }
+        Ok(())
     }
 
     /// Fx65 - LD Vx, [I]
@@ -768,77 +1654,819 @@ impl<'a> Chip8<'a> {
     ///
     /// The interpreter reads values from memory starting at location `I` into
     /// registers V0 through Vx.
-    fn ld_vx_i(&mut self, instruction: Instruction) {
-        (0..=instruction.x()).for_each(|register| {
+    ///
+    /// Addresses past the end of the memory wrap around to its start, as
+    /// does `I`.
+    fn ld_vx_i(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
+        (0..=instruction.x()).try_for_each(|register| {
             self.registers.insert(
                 register,
-                self.memory.read(self.i_register.add(register as u16)),
+                self.memory.read(self.i_register.add(register as u16))?,
             );
-        });
+            Ok(())
+        })?;
         if self.quirks.contains(&Quirks::IRegisterIncrementedWithX) {
             self.i_register
-                .set(self.i_register.get() + instruction.x() as u16 + 1);
+                .set(self.i_register.add(instruction.x() as u16 + 1));
         }
+        Ok(())
     }
 
-    /// Fx75 - Store V0..VX in RPL user flags (x <= 7)
+    /// Fx75 - Store V0..VX in RPL user flags (x <= 7 on SUPER-CHIP)
     fn load_rpl_flags(&mut self, instruction: Instruction) {
-        let register_quantity = match self.mode {
-            ChipMode::XOChip => &&instruction.x(),
-            ChipMode::SuperChip if instruction.x() <= 7 => &&instruction.x(),
-            _ => panic!(
-                "Unable to load RPL {} flags on {} platform.",
-                instruction.x(),
-                self.mode
-            ),
-        };
         self.memory.write_rpl_flags(
-            &self
-                .registers
-                .iter()
-                .filter(|(i, _)| i < register_quantity)
-                .map(|(i, _)| self.registers[i])
+            &(0..=instruction.x())
+                .map(|register| self.registers[&register])
                 .collect::<Vec<_>>(),
         );
     }
 
-    /// Fx85 - Read V0..VX from RPL user flags (x <= 7)
+    /// Fx85 - Read V0..VX from RPL user flags (x <= 7 on SUPER-CHIP)
     fn read_rpl_flags(&mut self, instruction: Instruction) {
-        let register_quantity = match self.mode {
-            ChipMode::XOChip => &&instruction.x(),
-            ChipMode::SuperChip if instruction.x() <= 7 => &&instruction.x(),
-            _ => panic!(
-                "Unable to load RPL {} flags on {} platform.",
-                instruction.x(),
-                self.mode
+        let flags = self.memory.read_rpl_flags()[..=instruction.x() as usize].to_vec();
+        flags.into_iter().enumerate().for_each(|(register, flag)| {
+            self.registers.insert(register as u8, flag);
+        });
+    }
+
+    #[cfg(feature = "stats")]
+    fn count_opcode(&mut self, instruction: &Instruction) {
+        let name = mnemonic(instruction.value(), &self.mode);
+        match self.opcode_counts.get_mut(name) {
+            Some(count) => *count += 1,
+            None => {
+                self.opcode_counts.insert(name.to_string(), 1);
+            }
+        }
+    }
+
+    fn skip_next_instruction(&mut self) {
+        let is_long = self.mode == ChipMode::XOChip
+            && self.memory.fetch(self.program_counter) == Some(0xF000);
+        self.advance_program_counter(if is_long { 4 } else { 2 });
+    }
+
+    fn next_instruction(&mut self) -> Result<Instruction, Chip8Error> {
+        let pc = self.program_counter;
+        let instruction_bytes = self
+            .memory
+            .fetch(pc)
+            .ok_or(Chip8Error::ProgramCounterOutOfBounds { pc })?;
+        self.advance_program_counter(2);
+        Ok(Instruction::new(instruction_bytes))
+    }
+
+    /// Moves the program counter forward, wrapping around the end of the
+    /// memory.
+    fn advance_program_counter(&mut self, offset: u16) {
+        self.program_counter =
+            self.program_counter.wrapping_add(offset) & self.memory.get_memory_size();
+    }
+
+    /// Leaves the program counter at the instruction at `pc` that made the
+    /// invalid memory access.
+    fn memory_violation(&mut self, error: MemoryError, pc: u16) -> Chip8Error {
+        self.program_counter = pc;
+        Chip8Error::MemoryViolation { error, pc }
+    }
+
+    /// Leaves the program counter at the `CALL` or `RET` at `pc` that
+    /// overflowed or underflowed the stack.
+    fn stack_violation(&mut self, error: StackError, pc: u16) -> Chip8Error {
+        self.program_counter = pc;
+        match error {
+            StackError::Overflow => Chip8Error::StackOverflow { pc },
+            StackError::Underflow => Chip8Error::StackUnderflow { pc },
+        }
+    }
+
+    /// Moves the program counter back to the current instruction, so it
+    /// is executed again.
+    fn rewind_program_counter(&mut self) {
+        self.program_counter = self.program_counter.wrapping_sub(2) & self.memory.get_memory_size();
+    }
+}
+
+impl std::fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chip8Error::IllegalInstruction { opcode, pc, mode } => write!(
+                f,
+                "Illegal instruction {} at 0x{pc:04X} for {mode}",
+                Instruction::new(*opcode)
+            ),
+            Chip8Error::ProgramCounterOutOfBounds { pc } => {
+                write!(f, "Program counter 0x{pc:04X} is out of memory")
+            }
+            Chip8Error::UnimplementedInstruction { opcode, pc, mode } => write!(
+                f,
+                "Instruction {} at 0x{pc:04X} is not implemented for {mode} yet",
+                Instruction::new(*opcode)
             ),
+            Chip8Error::MemoryViolation { error, pc } => {
+                write!(f, "{error} by the instruction at 0x{pc:04X}")
+            }
+            Chip8Error::StackOverflow { pc } => {
+                write!(f, "Stack overflow by the subroutine call at 0x{pc:04X}")
+            }
+            Chip8Error::StackUnderflow { pc } => {
+                write!(f, "Stack underflow by the return at 0x{pc:04X}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Machine without quirks that runs `program` one instruction per
+    /// frame with a fixed seed.
+    fn machine(program: &[u8], mode: &ChipMode, quirks: &HashSet<Quirks>) -> Chip8 {
+        Chip8::with_seed(Rom::from_bytes(program), mode, quirks, 1, None, 0).unwrap()
+    }
+
+    /// Executes `opcode` of the `8FY_` form with VF = `vf` and V1 = `vy`
+    /// and returns VF, which must hold the flag, not the result.
+    fn run_with_vf_as_vx(opcode: u16, vf: u8, vy: u8) -> u8 {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&opcode.to_be_bytes(), &ChipMode::Chip8, &quirks);
+        chip8.set_register(0xF, vf);
+        chip8.set_register(0x1, vy);
+        chip8.step().unwrap();
+        chip8.register(0xF)
+    }
+
+    #[test]
+    fn add_vx_vy_sets_carry_on_overflow() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x81, 0x24], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0xFF);
+        chip8.set_register(0x2, 0x02);
+
+        assert_eq!(chip8.step().unwrap(), StepOutcome::Executed);
+        assert_eq!(chip8.register(0x1), 0x01);
+        assert_eq!(chip8.register(0xF), 1);
+    }
+
+    #[test]
+    fn add_vx_vy_clears_carry_without_overflow() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x81, 0x24], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0x10);
+        chip8.set_register(0x2, 0x20);
+        chip8.set_register(0xF, 1);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x1), 0x30);
+        assert_eq!(chip8.register(0xF), 0);
+    }
+
+    #[test]
+    fn shifts_vy_into_vx_without_shift_quirk() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x81, 0x26, 0x83, 0x4E], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0xF0);
+        chip8.set_register(0x2, 0x03);
+        chip8.set_register(0x3, 0x0F);
+        chip8.set_register(0x4, 0x81);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x1), 0x01);
+        assert_eq!(chip8.register(0x2), 0x03);
+        assert_eq!(chip8.register(0xF), 1);
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x3), 0x02);
+        assert_eq!(chip8.register(0x4), 0x81);
+        assert_eq!(chip8.register(0xF), 1);
+    }
+
+    #[test]
+    fn shifts_vx_in_place_with_shift_quirk() {
+        let quirks = HashSet::from([Quirks::ShiftIgnoreVY]);
+        let mut chip8 = machine(&[0x81, 0x26, 0x83, 0x4E], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0xF0);
+        chip8.set_register(0x2, 0x03);
+        chip8.set_register(0x3, 0x0F);
+        chip8.set_register(0x4, 0x81);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x1), 0x78);
+        assert_eq!(chip8.register(0x2), 0x03);
+        assert_eq!(chip8.register(0xF), 0);
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x3), 0x1E);
+        assert_eq!(chip8.register(0x4), 0x81);
+        assert_eq!(chip8.register(0xF), 0);
+    }
+
+    #[test]
+    fn ld_b_vx_stores_decimal_digits() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xF1, 0x33, 0xF2, 0x33], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 255);
+        chip8.set_register(0x2, 7);
+        chip8.set_i(0x300);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.dump_memory(0x300..0x303), [2, 5, 5]);
+        chip8.step().unwrap();
+        assert_eq!(chip8.dump_memory(0x300..0x303), [0, 0, 7]);
+    }
+
+    #[test]
+    fn drw_sets_vf_when_a_pixel_is_erased() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xD0, 0x15, 0xD0, 0x15], &ChipMode::Chip8, &quirks);
+        // The font digit 0 is at the start of the memory.
+        chip8.set_i(0x000);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0xF), 0);
+        assert!(chip8.display().pixel(0, 0, Plane::First));
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0xF), 1);
+        assert!(chip8.display().buffer().iter().all(|&pixel| !pixel));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn state_json_has_versioned_schema() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(
+            &[0x22, 0x04, 0x00, 0x00, 0x00, 0xEE],
+            &ChipMode::Chip8,
+            &quirks,
+        );
+        chip8.set_register(0x3, 0x42);
+        chip8.set_i(0x0ABC);
+        chip8.step().unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&chip8.state_json()).unwrap();
+        assert_eq!(json["version"], crate::state::STATE_JSON_VERSION);
+        assert_eq!(json["pc"], 0x204);
+        assert_eq!(json["i"], 0x0ABC);
+        assert_eq!(json["registers"].as_array().unwrap().len(), 16);
+        assert_eq!(json["registers"][3], 0x42);
+        assert_eq!(json["stack"], serde_json::json!([0x202]));
+        assert_eq!(json["memory"].as_str().unwrap().len(), 4096 / 3 * 4 + 4);
+    }
+
+    #[test]
+    fn ld_i_vx_at_the_top_of_memory_wraps_i() {
+        let quirks = HashSet::from([Quirks::IRegisterIncrementedWithX]);
+        let mut chip8 = machine(&[0xFF, 0x55], &ChipMode::Chip8, &quirks);
+        (0..=0xF).for_each(|x| chip8.set_register(x, x + 1));
+        chip8.set_i(0x0FF0);
+
+        chip8.step().unwrap();
+        assert_eq!(
+            &chip8.memory.map()[0xFF0..=0xFFF],
+            &(1..=16).collect::<Vec<u8>>()
+        );
+        assert_eq!(chip8.i(), 0x000);
+    }
+
+    #[test]
+    fn ld_i_vx_past_the_top_of_memory_is_a_violation() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xFF, 0x55], &ChipMode::Chip8, &quirks);
+        chip8.set_i(0x0FF8);
+
+        assert!(matches!(
+            chip8.step(),
+            Err(Chip8Error::MemoryViolation {
+                error: MemoryError::WriteToReserved(0x000),
+                pc: 0x200,
+            })
+        ));
+    }
+
+    #[test]
+    fn ld_vx_i_past_the_top_of_extended_memory_wraps() {
+        let quirks = HashSet::from([Quirks::IRegisterIncrementedWithX]);
+        let mut chip8 = machine(&[0xFF, 0x65], &ChipMode::XOChip, &quirks);
+        chip8.set_i(0xFFF8);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x8), chip8.memory.map()[0x000]);
+        assert_eq!(chip8.i(), 0x0008);
+    }
+
+    #[test]
+    fn step_back_undoes_the_last_frames() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x71, 0x01, 0x12, 0x00], &ChipMode::Chip8, &quirks);
+        chip8.set_ticks_per_frame(2);
+        chip8.set_rewind_depth(2);
+        (0..3).for_each(|_| {
+            chip8.step_frame().unwrap();
+        });
+        assert_eq!(chip8.register(0x1), 3);
+
+        assert!(chip8.step_back());
+        assert_eq!(chip8.register(0x1), 2);
+        assert!(chip8.step_back());
+        assert_eq!(chip8.register(0x1), 1);
+        assert!(!chip8.step_back());
+        assert_eq!(chip8.register(0x1), 1);
+    }
+
+    #[test]
+    fn ld_vx_k_takes_scripted_input() {
+        let quirks = HashSet::from([Quirks::WaitForKeyRelease]);
+        let mut chip8 = machine(&[0xF1, 0x0A, 0x12, 0x02], &ChipMode::Chip8, &quirks);
+        let mut frame = 0;
+        let mut input = |keyboard: &mut Keyboard| {
+            match frame {
+                1 => keyboard.press_key(0x5),
+                2 => keyboard.release_key(0x5),
+                _ => {}
+            }
+            frame += 1;
         };
-        self.memory
-            .read_rpl_flags()
+
+        chip8.step_frame_with_input(&mut input).unwrap();
+        chip8.step_frame_with_input(&mut input).unwrap();
+        assert_eq!(chip8.pc(), 0x200);
+        chip8.step_frame_with_input(&mut input).unwrap();
+        assert_eq!(chip8.register(0x1), 0x5);
+        assert_eq!(chip8.pc(), 0x202);
+    }
+
+    #[test]
+    fn index_buffer_combines_the_planes() {
+        let quirks = HashSet::new();
+        // Draws the font digit 0 on the first plane, then four pixels to
+        // the right the digit 1 on the first plane and 2 on the second one.
+        let program = [
+            0xF1, 0x01, 0xD0, 0x05, 0xF3, 0x01, 0x60, 0x04, 0xA0, 0x05, 0xD0, 0x15,
+        ];
+        let mut chip8 = machine(&program, &ChipMode::XOChip, &quirks);
+        (0..6).for_each(|_| {
+            chip8.step().unwrap();
+        });
+
+        let display = chip8.display();
+        let indexes = display.index_buffer();
+        assert_eq!(indexes.len(), display.width() * display.height());
+        (0..display.height()).for_each(|y| {
+            (0..display.width()).for_each(|x| {
+                assert_eq!(
+                    indexes[x + y * display.width()] as usize,
+                    display.color_at(x, y).index()
+                );
+            });
+        });
+        assert_eq!(&indexes[..8], &[1, 1, 1, 1, 2, 2, 3, 2]);
+    }
+
+    #[test]
+    fn endless_recursion_is_a_stack_overflow() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x22, 0x00], &ChipMode::Chip8, &quirks);
+        (0..16).for_each(|_| {
+            chip8.step().unwrap();
+        });
+
+        assert!(matches!(
+            chip8.step(),
+            Err(Chip8Error::StackOverflow { pc: 0x200 })
+        ));
+        assert_eq!(chip8.pc(), 0x200);
+    }
+
+    #[test]
+    fn ret_without_call_is_a_stack_underflow() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x00, 0xEE], &ChipMode::Chip8, &quirks);
+
+        assert!(matches!(
+            chip8.step(),
+            Err(Chip8Error::StackUnderflow { pc: 0x200 })
+        ));
+    }
+
+    #[test]
+    fn drw_with_zero_rows_on_chip8_draws_nothing() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xD0, 0x10], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0xF, 1);
+
+        assert_eq!(chip8.step().unwrap(), StepOutcome::Executed);
+        assert!(chip8.display().buffer().iter().all(|&pixel| !pixel));
+        assert_eq!(chip8.register(0xF), 0);
+    }
+
+    #[test]
+    fn plane_above_3_is_illegal() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xF4, 0x01], &ChipMode::XOChip, &quirks);
+
+        assert!(matches!(
+            chip8.step(),
+            Err(Chip8Error::IllegalInstruction {
+                opcode: 0xF401,
+                pc: 0x200,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rpl_flags_above_7_are_illegal_on_super_chip() {
+        let quirks = HashSet::new();
+        [0xF875, 0xF885].into_iter().for_each(|opcode: u16| {
+            let mut chip8 = machine(&opcode.to_be_bytes(), &ChipMode::SuperChip, &quirks);
+            assert!(matches!(
+                chip8.step(),
+                Err(Chip8Error::IllegalInstruction { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn rpl_flags_store_and_read_v0_to_vx() {
+        let quirks = HashSet::new();
+        // Stores V0..VF, clears V0..VF with Fx65 from zeroed memory and
+        // reads back V0..V9.
+        let program = [0xFF, 0x75, 0xA3, 0x00, 0xFF, 0x65, 0xF9, 0x85];
+        let mut chip8 = machine(&program, &ChipMode::XOChip, &quirks);
+        (0..=0xF).for_each(|x| chip8.set_register(x, 0x10 + x));
+
+        (0..4).for_each(|_| {
+            chip8.step().unwrap();
+        });
+        (0..=9).for_each(|x| assert_eq!(chip8.register(x), 0x10 + x));
+        (0xA..=0xF).for_each(|x| assert_eq!(chip8.register(x), 0));
+    }
+
+    #[test]
+    fn ld_f_vx_uses_the_low_nibble() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xF1, 0x29], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0x1A);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.i(), 0xA * 5);
+    }
+
+    #[test]
+    fn sub_vx_vy_sets_not_borrow() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x81, 0x25, 0x83, 0x45], &ChipMode::Chip8, &quirks);
+        chip8.set_register(0x1, 0x30);
+        chip8.set_register(0x2, 0x10);
+        chip8.set_register(0x3, 0x10);
+        chip8.set_register(0x4, 0x30);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x1), 0x20);
+        assert_eq!(chip8.register(0xF), 1);
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x3), 0xE0);
+        assert_eq!(chip8.register(0xF), 0);
+    }
+
+    #[test]
+    fn add_vf_vy_keeps_the_carry() {
+        assert_eq!(run_with_vf_as_vx(0x8F14, 0xFF, 0x02), 1);
+        assert_eq!(run_with_vf_as_vx(0x8F14, 0x10, 0x20), 0);
+    }
+
+    #[test]
+    fn sub_vf_vy_keeps_not_borrow() {
+        assert_eq!(run_with_vf_as_vx(0x8F15, 0x30, 0x10), 1);
+        assert_eq!(run_with_vf_as_vx(0x8F15, 0x10, 0x30), 0);
+    }
+
+    #[test]
+    fn shr_vf_vy_keeps_the_shifted_out_bit() {
+        assert_eq!(run_with_vf_as_vx(0x8F16, 0x00, 0x03), 1);
+        assert_eq!(run_with_vf_as_vx(0x8F16, 0xFF, 0x02), 0);
+    }
+
+    #[test]
+    fn shl_vf_vy_keeps_the_shifted_out_bit() {
+        assert_eq!(run_with_vf_as_vx(0x8F1E, 0x00, 0x81), 1);
+        assert_eq!(run_with_vf_as_vx(0x8F1E, 0xFF, 0x7F), 0);
+    }
+
+    #[test]
+    fn ld_hf_vx_points_to_large_hex_digits() {
+        let quirks = HashSet::new();
+        [0x0A, 0x1A].into_iter().for_each(|digit| {
+            let mut chip8 = machine(&[0xF1, 0x30], &ChipMode::XOChip, &quirks);
+            chip8.set_register(0x1, digit);
+
+            chip8.step().unwrap();
+            assert_eq!(chip8.i(), 16 * 5 + 0xA * 10);
+            assert_eq!(
+                chip8.dump_memory(chip8.i()..chip8.i() + 2),
+                &[0x7E, 0xFF],
+                "digit {digit:02X}"
+            );
+        });
+    }
+
+    #[test]
+    fn state_with_larger_memory_is_rejected() {
+        let quirks = HashSet::new();
+        let state = machine(&[0x12, 0x00], &ChipMode::XOChip, &quirks).save_state();
+        let mut chip8 = machine(&[0x61, 0x01], &ChipMode::Chip8, &quirks);
+
+        assert!(matches!(
+            chip8.load_state(&state),
+            Err(MemoryError::LoadOutOfBounds {
+                addr: 0,
+                len: 0x10000
+            })
+        ));
+        assert_eq!(chip8.dump_memory(0x200..0x202), &[0x61, 0x01]);
+    }
+
+    #[test]
+    fn pitch_covers_the_whole_register_range() {
+        let quirks = HashSet::new();
+        [(0, 1587), (64, 4000), (255, 63082)]
+            .into_iter()
+            .for_each(|(vx, pitch)| {
+                let mut chip8 = machine(&[0xF1, 0x3A], &ChipMode::XOChip, &quirks);
+                chip8.set_register(0x1, vx);
+
+                chip8.step().unwrap();
+                assert_eq!(chip8.pitch(), pitch, "Vx = {vx}");
+            });
+    }
+
+    #[test]
+    fn same_seed_gives_same_random_numbers() {
+        let quirks = HashSet::new();
+        let program = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0x0F, 0xC3, 0xF0, 0x12, 0x00];
+        let run = |seed| {
+            let mut chip8 = Chip8::with_seed(
+                Rom::from_bytes(program),
+                &ChipMode::Chip8,
+                &quirks,
+                1,
+                None,
+                seed,
+            )
+            .unwrap();
+            (0..20).for_each(|_| {
+                chip8.step().unwrap();
+            });
+            (0..4).map(|x| chip8.register(x)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+
+    #[test]
+    fn add_i_vx_sets_vf_past_0x0fff_with_quirk() {
+        let quirks = HashSet::from([Quirks::AddIOverflowSetsVF]);
+        [(0x0FFE, 0, 0x0FFF), (0x0FFF, 1, 0x0000)]
+            .into_iter()
+            .for_each(|(i, vf, wrapped_i)| {
+                let mut chip8 = machine(&[0xF1, 0x1E], &ChipMode::Chip8, &quirks);
+                chip8.set_i(i);
+                chip8.set_register(0x1, 1);
+                chip8.set_register(0xF, 0xAA);
+
+                chip8.step().unwrap();
+                assert_eq!(chip8.i(), wrapped_i);
+                assert_eq!(chip8.register(0xF), vf, "I = {i:04X}");
+            });
+    }
+
+    #[test]
+    fn add_i_vx_keeps_vf_without_quirk() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xF1, 0x1E], &ChipMode::Chip8, &quirks);
+        chip8.set_i(0x0FFF);
+        chip8.set_register(0x1, 1);
+        chip8.set_register(0xF, 0xAA);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.i(), 0x0000);
+        assert_eq!(chip8.register(0xF), 0xAA);
+    }
+
+    #[test]
+    fn set_pc_wraps_at_the_end_of_memory() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x12, 0x00], &ChipMode::Chip8, &quirks);
+
+        chip8.set_pc(0x0FFE);
+        assert_eq!(chip8.pc(), 0x0FFE);
+        chip8.set_pc(0x1202);
+        assert_eq!(chip8.pc(), 0x0202);
+    }
+
+    #[test]
+    fn executes_at_the_last_valid_address() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x12, 0x00], &ChipMode::Chip8, &quirks);
+        chip8.load_bytes_at(0x0FFE, &[0x61, 0x2A]).unwrap();
+        chip8.set_pc(0x0FFE);
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.register(0x1), 0x2A);
+        assert_eq!(chip8.pc(), 0x0000);
+    }
+
+    #[test]
+    fn fetch_past_the_end_of_memory_is_an_error() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x12, 0x00], &ChipMode::Chip8, &quirks);
+        chip8.set_pc(0x0FFF);
+
+        assert!(matches!(
+            chip8.step(),
+            Err(Chip8Error::ProgramCounterOutOfBounds { pc: 0x0FFF })
+        ));
+        assert_eq!(chip8.pc(), 0x0FFF);
+    }
+
+    /// Runs `B210` with V0 = 1 and V2 = 5 and returns the new PC.
+    fn jump_target<const N: usize>(mode: &ChipMode, quirks: [Quirks; N]) -> u16 {
+        let quirks = HashSet::from(quirks);
+        let mut chip8 = machine(&[0xB2, 0x10], mode, &quirks);
+        chip8.set_register(0x0, 1);
+        chip8.set_register(0x2, 5);
+        chip8.step().unwrap();
+        chip8.pc()
+    }
+
+    #[test]
+    fn jump_with_offset_uses_v0_or_vx_per_mode() {
+        assert_eq!(jump_target(&ChipMode::Chip8, []), 0x211);
+        assert_eq!(jump_target(&ChipMode::SuperChip, []), 0x215);
+        assert_eq!(jump_target(&ChipMode::XOChip, []), 0x211);
+    }
+
+    #[test]
+    fn jump_with_offset_quirks_override_the_mode() {
+        assert_eq!(jump_target(&ChipMode::Chip8, [Quirks::JumpWithX]), 0x215);
+        assert_eq!(jump_target(&ChipMode::XOChip, [Quirks::JumpWithX]), 0x215);
+        assert_eq!(
+            jump_target(&ChipMode::SuperChip, [Quirks::JumpWithV0]),
+            0x211
+        );
+        assert_eq!(
+            jump_target(&ChipMode::Chip8, [Quirks::JumpWithX, Quirks::JumpWithV0]),
+            0x211
+        );
+    }
+
+    /// Draws the font digit 0, scrolls the screen with `scroll` and tells
+    /// whether any pixel is still lit.
+    fn lit_after_scroll(mode: &ChipMode, scroll: impl FnOnce(&mut Display)) -> bool {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0xA0, 0x00, 0xD0, 0x05], mode, &quirks);
+        chip8.step().unwrap();
+        chip8.step().unwrap();
+        assert!(chip8.display().buffer().contains(&true));
+        scroll(&mut chip8.display);
+        chip8.display().buffer().contains(&true)
+    }
+
+    /// Asserts that scrolling the screen `lines` rows down and up clears it.
+    fn assert_scroll_clears(mode: &ChipMode, lines: u8) {
+        assert!(!lit_after_scroll(mode, |display| display.scroll_n_lines_down(lines)));
+        assert!(!lit_after_scroll(mode, |display| display.scroll_n_lines_up(lines)));
+    }
+
+    #[test]
+    fn scrolling_by_the_screen_height_clears_it() {
+        [ChipMode::SuperChip, ChipMode::XOChip]
             .iter()
-            .filter(|x| x < register_quantity)
-            .enumerate()
-            .for_each(|(i, &x)| {
-                self.registers.insert(i as u8, x);
+            .for_each(|mode| {
+                let height = machine(&[0x00, 0xE0], mode, &HashSet::new())
+                    .display()
+                    .height() as u8;
+                assert_scroll_clears(mode, height);
             });
     }
 
-    fn skip_next_instruction(&mut self) {
-        if self.mode == &ChipMode::XOChip {
-            if self.next_instruction().nibbles() == (0xF, 0, 0, 0) {
-                self.program_counter += 2;
-            }
-        } else {
-            self.program_counter += 2;
-        }
+    #[test]
+    fn scrolling_past_the_screen_height_clears_it() {
+        [ChipMode::SuperChip, ChipMode::XOChip]
+            .iter()
+            .for_each(|mode| {
+                let height = machine(&[0x00, 0xE0], mode, &HashSet::new())
+                    .display()
+                    .height() as u8;
+                assert_scroll_clears(mode, height + 1);
+                assert_scroll_clears(mode, u8::MAX);
+            });
+    }
+
+    /// Lights the top left pixel on both planes, selects the second plane
+    /// and runs `00E0`. Returns whether the pixel is still set on the first
+    /// and on the second plane.
+    fn pixels_after_cls(mode: &ChipMode) -> (bool, bool) {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x00, 0xE0], mode, &quirks);
+        chip8.display.blit(0, 0, &[true], 1, Plane::Both);
+        chip8.display.set_plane(Plane::Second);
+
+        chip8.step().unwrap();
+        let display = chip8.display();
+        (
+            display.pixel(0, 0, Plane::First),
+            display.pixel(0, 0, Plane::Second),
+        )
     }
 
-    fn next_instruction(&mut self) -> Instruction {
-        let instruction_bytes = u16::from_be_bytes([
-            self.memory.read(self.program_counter),
-            self.memory.read(self.program_counter + 1),
-        ]);
-        self.program_counter += 2;
-        Instruction::new(instruction_bytes)
+    #[test]
+    fn cls_clears_the_whole_screen_before_xo_chip() {
+        [ChipMode::Chip8, ChipMode::SuperChip]
+            .iter()
+            .for_each(|mode| {
+                assert_eq!(pixels_after_cls(mode), (false, false));
+            });
+    }
+
+    #[test]
+    fn cls_clears_only_the_selected_planes_on_xo_chip() {
+        assert_eq!(pixels_after_cls(&ChipMode::XOChip), (true, false));
+    }
+
+    #[test]
+    fn mega_chip_switches_to_the_256x192_screen() {
+        let quirks = HashSet::new();
+        // MEGAON, draws the font digit 0 at (248, 187), MEGAOFF.
+        let program = [
+            0x00, 0x11, 0x60, 0xF8, 0x61, 0xBB, 0xA0, 0x00, 0xD0, 0x15, 0x00, 0x10,
+        ];
+        let mut chip8 = machine(&program, &ChipMode::MegaChip, &quirks);
+        chip8.step().unwrap();
+        assert_eq!(chip8.display().resolution(), (256, 192));
+
+        (0..4).for_each(|_| {
+            chip8.step().unwrap();
+        });
+        let display = chip8.display();
+        assert_eq!(display.buffer().len(), 256 * 192);
+        assert_eq!(display.display_bitplane().len(), 256 * 192);
+        assert!(display.pixel(248, 187, Plane::First));
+        assert!(display.pixel(251, 191, Plane::First));
+
+        chip8.step().unwrap();
+        assert_eq!(chip8.display().resolution(), (64, 32));
+    }
+
+    #[test]
+    fn mega_chip_screen_survives_a_snapshot() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x00, 0x11, 0x00, 0x10], &ChipMode::MegaChip, &quirks);
+        chip8.step().unwrap();
+        let state = chip8.save_state();
+        chip8.step().unwrap();
+
+        chip8.load_state(&state).unwrap();
+        assert_eq!(chip8.display().resolution(), (256, 192));
+    }
+
+    #[test]
+    fn dump_memory_clamps_the_range_to_the_memory() {
+        let quirks = HashSet::new();
+        let mut chip8 = machine(&[0x12, 0x00], &ChipMode::Chip8, &quirks);
+        chip8.load_bytes_at(0x0FFE, &[0xAB, 0xCD]).unwrap();
+
+        assert_eq!(chip8.dump_memory(0x0FFE..0x1100), [0xAB, 0xCD]);
+        assert_eq!(chip8.dump_memory(0x0FFF..=0xFFFF), [0xCD]);
+        assert_eq!(chip8.dump_memory(..=0xFFFF).len(), 0x1000);
+        assert!(chip8.dump_memory(0x1000..).is_empty());
+        let (start, end) = (0x300, 0x200);
+        assert!(chip8.dump_memory(start..end).is_empty());
+    }
+
+    #[test]
+    fn drw_reports_collisions_per_plane() {
+        let quirks = HashSet::new();
+        // Selects both planes and draws a row of 0x80 on the first plane
+        // and 0x40 on the second one at (0, 0).
+        let program = [0xF3, 0x01, 0xA2, 0x06, 0xD0, 0x01, 0x80, 0x40];
+        let mut chip8 = machine(&program, &ChipMode::XOChip, &quirks);
+        chip8.display.blit(0, 0, &[true], 1, Plane::First);
+        (0..3).for_each(|_| {
+            chip8.step().unwrap();
+        });
+
+        assert_eq!(
+            chip8.plane_collisions(),
+            PlaneCollisions {
+                first_plane: true,
+                second_plane: false,
+            }
+        );
+        assert_eq!(chip8.register(0xF), 1);
+        assert!(!chip8.display().pixel(0, 0, Plane::First));
+        assert!(chip8.display().pixel(1, 0, Plane::Second));
     }
 }
@@ -0,0 +1,78 @@
+/// Kind of memory access a watchpoint reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Both,
+}
+
+impl WatchKind {
+    /// Whether a watchpoint of this kind is triggered by `access`.
+    pub fn matches(&self, access: WatchKind) -> bool {
+        *self == WatchKind::Both || *self == access
+    }
+}
+
+/// A memory access that triggered a watchpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// The watched address.
+    pub addr: u16,
+    /// The access that happened, either `Read` or `Write`.
+    pub kind: WatchKind,
+    /// Address of the instruction that made the access.
+    pub pc: u16,
+}
+
+/// Result of `Chip8::step` and `Chip8::step_frame`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The instruction (or the whole frame) was executed.
+    Executed,
+    /// Nothing was executed because the machine is halted.
+    Halted,
+    /// Nothing was executed because the machine is paused.
+    Paused,
+    /// Execution stopped before the instruction at this address. The next
+    /// step executes it.
+    BreakpointHit(u16),
+    /// An executed instruction accessed a watched address.
+    WatchpointHit(WatchHit),
+}
+
+/// Why `Chip8::run_until_halt` stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The program exited with `00FD`.
+    Halted,
+    /// `max_cycles` instructions were executed.
+    CycleLimit,
+    /// Execution stopped before the instruction at this address.
+    BreakpointHit(u16),
+    /// The last executed instruction accessed a watched address.
+    WatchpointHit(WatchHit),
+}
+
+/// Result of `Chip8::run_until_halt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunResult {
+    /// Number of executed instructions.
+    pub cycles: u64,
+    pub reason: StopReason,
+}
+
+/// An instruction about to be executed, passed to the tracer installed with
+/// `Chip8::set_tracer`.
+///
+/// Registers are captured before the instruction runs, so the changes an
+/// instruction made are seen in the next event.
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    /// Address of the instruction.
+    pub pc: u16,
+    pub opcode: u16,
+    /// The instruction as shown by `disassemble`, e.g. `LD V1, 0x0A`.
+    pub mnemonic: String,
+    pub registers: [u8; 16],
+    pub i: u16,
+}
@@ -0,0 +1,193 @@
+use crate::instruction::Instruction;
+use crate::op::{Op, decode};
+use crate::platform::ChipMode;
+
+/// Decodes an instruction into a human-readable mnemonic, e.g. `DRW V1, V2, 5`
+/// or `LD I, 0x2A0`.
+///
+/// Instructions that are not supported by `mode` are shown as `unknown`.
+pub fn disassemble(instruction: Instruction, mode: &ChipMode) -> String {
+    let Some(op) = decode(instruction.value(), mode) else {
+        return "unknown".to_string();
+    };
+    match op {
+        Op::Scd(n) => format!("SCD {n}"),
+        Op::Scu(n) => format!("SCU {n}"),
+        Op::Cls => "CLS".to_string(),
+        Op::Ret => "RET".to_string(),
+        Op::Scr => "SCR".to_string(),
+        Op::Scl => "SCL".to_string(),
+        Op::Exit => "EXIT".to_string(),
+        Op::Low => "LOW".to_string(),
+        Op::High => "HIGH".to_string(),
+        Op::Sys(nnn) | Op::Jp(nnn) => format!("JP 0x{nnn:03X}"),
+        Op::Call(nnn) => format!("CALL 0x{nnn:03X}"),
+        Op::SeByte { x, kk } => format!("SE V{x:X}, 0x{kk:02X}"),
+        Op::SneByte { x, kk } => format!("SNE V{x:X}, 0x{kk:02X}"),
+        Op::Save { x, y } => format!("SAVE V{x:X}..V{y:X}"),
+        Op::Load { x, y } => format!("LOAD V{x:X}..V{y:X}"),
+        Op::SeReg { x, y } => format!("SE V{x:X}, V{y:X}"),
+        Op::LdByte { x, kk } => format!("LD V{x:X}, 0x{kk:02X}"),
+        Op::AddByte { x, kk } => format!("ADD V{x:X}, 0x{kk:02X}"),
+        Op::LdReg { x, y } => format!("LD V{x:X}, V{y:X}"),
+        Op::Or { x, y } => format!("OR V{x:X}, V{y:X}"),
+        Op::And { x, y } => format!("AND V{x:X}, V{y:X}"),
+        Op::Xor { x, y } => format!("XOR V{x:X}, V{y:X}"),
+        Op::AddReg { x, y } => format!("ADD V{x:X}, V{y:X}"),
+        Op::Sub { x, y } => format!("SUB V{x:X}, V{y:X}"),
+        Op::Shr { x, y } => format!("SHR V{x:X}, V{y:X}"),
+        Op::Subn { x, y } => format!("SUBN V{x:X}, V{y:X}"),
+        Op::Shl { x, y } => format!("SHL V{x:X}, V{y:X}"),
+        Op::SneReg { x, y } => format!("SNE V{x:X}, V{y:X}"),
+        Op::LdI(nnn) => format!("LD I, 0x{nnn:03X}"),
+        Op::JpV0(nnn) => format!("JP V0, 0x{nnn:03X}"),
+        Op::Rnd { x, kk } => format!("RND V{x:X}, 0x{kk:02X}"),
+        Op::Drw { x, y, n } => format!("DRW V{x:X}, V{y:X}, {n}"),
+        Op::Skp(x) => format!("SKP V{x:X}"),
+        Op::Sknp(x) => format!("SKNP V{x:X}"),
+        Op::LdILong => "LD I, long".to_string(),
+        Op::Plane(x) => format!("PLANE {x}"),
+        Op::Audio => "AUDIO".to_string(),
+        Op::LdVxDt(x) => format!("LD V{x:X}, DT"),
+        Op::LdVxK(x) => format!("LD V{x:X}, K"),
+        Op::LdDtVx(x) => format!("LD DT, V{x:X}"),
+        Op::LdStVx(x) => format!("LD ST, V{x:X}"),
+        Op::AddIVx(x) => format!("ADD I, V{x:X}"),
+        Op::LdFVx(x) => format!("LD F, V{x:X}"),
+        Op::LdHfVx(x) => format!("LD HF, V{x:X}"),
+        Op::LdBVx(x) => format!("LD B, V{x:X}"),
+        Op::Pitch(x) => format!("PITCH V{x:X}"),
+        Op::LdIVx(x) => format!("LD [I], V{x:X}"),
+        Op::LdVxI(x) => format!("LD V{x:X}, [I]"),
+        Op::LdRVx(x) => format!("LD R, V{x:X}"),
+        Op::LdVxR(x) => format!("LD V{x:X}, R"),
+        Op::MegaOff => "MEGAOFF".to_string(),
+        Op::MegaOn => "MEGAON".to_string(),
+        Op::LdIHi(nn) => format!("LDHI 0x{nn:02X}"),
+        Op::LdPal(nn) => format!("LDPAL {nn}"),
+        Op::SprW(nn) => format!("SPRW {nn}"),
+        Op::SprH(nn) => format!("SPRH {nn}"),
+        Op::Alpha(nn) => format!("ALPHA 0x{nn:02X}"),
+        Op::DigiSnd(n) => format!("DIGISND {n}"),
+        Op::StopSnd => "STOPSND".to_string(),
+        Op::BMode(n) => format!("BMODE {n}"),
+        Op::CCol(nn) => format!("CCOL 0x{nn:02X}"),
+    }
+}
+
+/// The mnemonic of `opcode` without operands, e.g. `DRW` or `LD`. Cheaper
+/// than `disassemble` for labeling a lot of instructions.
+///
+/// For the opcodes `mode` supports it is the first word of `disassemble`.
+/// The others are `ILLEGAL`, while `disassemble` shows them as `unknown`.
+pub fn mnemonic(opcode: u16, mode: &ChipMode) -> &'static str {
+    let Some(op) = decode(opcode, mode) else {
+        return "ILLEGAL";
+    };
+    match op {
+        Op::Scd(_) => "SCD",
+        Op::Scu(_) => "SCU",
+        Op::Cls => "CLS",
+        Op::Ret => "RET",
+        Op::Scr => "SCR",
+        Op::Scl => "SCL",
+        Op::Exit => "EXIT",
+        Op::Low => "LOW",
+        Op::High => "HIGH",
+        Op::Sys(_) | Op::Jp(_) | Op::JpV0(_) => "JP",
+        Op::Call(_) => "CALL",
+        Op::SeByte { .. } | Op::SeReg { .. } => "SE",
+        Op::SneByte { .. } | Op::SneReg { .. } => "SNE",
+        Op::Save { .. } => "SAVE",
+        Op::Load { .. } => "LOAD",
+        Op::LdByte { .. }
+        | Op::LdReg { .. }
+        | Op::LdI(_)
+        | Op::LdILong
+        | Op::LdVxDt(_)
+        | Op::LdVxK(_)
+        | Op::LdDtVx(_)
+        | Op::LdStVx(_)
+        | Op::LdFVx(_)
+        | Op::LdHfVx(_)
+        | Op::LdBVx(_)
+        | Op::LdIVx(_)
+        | Op::LdVxI(_)
+        | Op::LdRVx(_)
+        | Op::LdVxR(_) => "LD",
+        Op::AddByte { .. } | Op::AddReg { .. } | Op::AddIVx(_) => "ADD",
+        Op::Or { .. } => "OR",
+        Op::And { .. } => "AND",
+        Op::Xor { .. } => "XOR",
+        Op::Sub { .. } => "SUB",
+        Op::Shr { .. } => "SHR",
+        Op::Subn { .. } => "SUBN",
+        Op::Shl { .. } => "SHL",
+        Op::Rnd { .. } => "RND",
+        Op::Drw { .. } => "DRW",
+        Op::Skp(_) => "SKP",
+        Op::Sknp(_) => "SKNP",
+        Op::Plane(_) => "PLANE",
+        Op::Audio => "AUDIO",
+        Op::Pitch(_) => "PITCH",
+        Op::MegaOff => "MEGAOFF",
+        Op::MegaOn => "MEGAON",
+        Op::LdIHi(_) => "LDHI",
+        Op::LdPal(_) => "LDPAL",
+        Op::SprW(_) => "SPRW",
+        Op::SprH(_) => "SPRH",
+        Op::Alpha(_) => "ALPHA",
+        Op::DigiSnd(_) => "DIGISND",
+        Op::StopSnd => "STOPSND",
+        Op::BMode(_) => "BMODE",
+        Op::CCol(_) => "CCOL",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mnemonic_is_the_first_word_of_disassembly() {
+        [
+            ChipMode::Chip8,
+            ChipMode::SuperChip,
+            ChipMode::XOChip,
+            ChipMode::MegaChip,
+        ]
+        .iter()
+        .for_each(|mode| {
+            (0..=u16::MAX).for_each(|opcode| {
+                let disassembly = disassemble(Instruction::new(opcode), mode);
+                let expected = match decode(opcode, mode) {
+                    Some(_) => disassembly.split(' ').next().unwrap(),
+                    None => {
+                        assert_eq!(disassembly, "unknown", "{opcode:04X} on {mode:?}");
+                        "ILLEGAL"
+                    }
+                };
+                assert_eq!(mnemonic(opcode, mode), expected, "{opcode:04X} on {mode:?}");
+            });
+        });
+    }
+
+    #[test]
+    fn save_is_unknown_before_xo_chip() {
+        let save = |mode| disassemble(Instruction::new(0x5122), mode);
+        assert_eq!(save(&ChipMode::Chip8), "unknown");
+        assert_eq!(save(&ChipMode::SuperChip), "unknown");
+        assert_eq!(save(&ChipMode::XOChip), "SAVE V1..V2");
+        assert_eq!(
+            disassemble(Instruction::new(0x5120), &ChipMode::Chip8),
+            "SE V1, V2"
+        );
+    }
+
+    #[test]
+    fn mnemonic_of_illegal_opcode() {
+        assert_eq!(mnemonic(0xE1FF, &ChipMode::Chip8), "ILLEGAL");
+        assert_eq!(mnemonic(0xF001, &ChipMode::Chip8), "ILLEGAL");
+        assert_eq!(mnemonic(0xF001, &ChipMode::XOChip), "PLANE");
+    }
+}
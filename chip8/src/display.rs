@@ -1,50 +1,139 @@
-use crate::platform::Quirks;
+use crate::platform::{ChipMode, Quirks};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashSet;
 
-type PixelErased = bool;
+/// What happened to the screen while a sprite was drawn.
+#[derive(Default, Clone, Copy)]
+pub struct DrawOutcome {
+    /// Whether any pixel was turned off.
+    pub pixel_erased: bool,
+    /// Number of sprite rows that turned off at least one pixel.
+    pub erased_rows: u8,
+    /// Number of sprite rows clipped at the bottom of the screen.
+    pub clipped_rows: u8,
+}
+
+impl DrawOutcome {
+    /// Combines outcomes of drawing on the different planes.
+    pub fn merge(self, other: DrawOutcome) -> DrawOutcome {
+        DrawOutcome {
+            pixel_erased: self.pixel_erased | other.pixel_erased,
+            erased_rows: self.erased_rows.max(other.erased_rows),
+            clipped_rows: self.clipped_rows.max(other.clipped_rows),
+        }
+    }
+}
+
+/// Planes a sprite erased a pixel on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlaneCollisions {
+    pub first_plane: bool,
+    pub second_plane: bool,
+}
+
+impl PlaneCollisions {
+    /// Adds the outcome of drawing on `plane`, either `First` or `Second`.
+    pub fn record(&mut self, plane: Plane, outcome: DrawOutcome) {
+        match plane {
+            Plane::First => self.first_plane |= outcome.pixel_erased,
+            Plane::Second => self.second_plane |= outcome.pixel_erased,
+            Plane::Both => panic!("Collisions are recorded per plane."),
+        }
+    }
 
-pub struct Display<'a> {
-    first_plane: [bool; 8192],
-    second_plane: [bool; 8192],
+    /// Whether a pixel was erased on any plane, as VF tells.
+    pub fn any(&self) -> bool {
+        self.first_plane || self.second_plane
+    }
+}
+
+pub struct Display {
+    /// Sized for the largest screen of the mode, see `max_resolution`.
+    first_plane: Vec<bool>,
+    second_plane: Vec<bool>,
+    /// SUPER-CHIP and XO-CHIP 128x64 mode.
     is_hires: bool,
+    /// 64x64 mode of the COSMAC VIP hires CHIP-8. It is independent of
+    /// `is_hires`, which still means the SUPER-CHIP 128x64 mode.
+    is_hires_64x64: bool,
+    /// MEGA-CHIP 256x192 mode, it takes precedence over `is_hires`.
+    is_mega: bool,
     current_plane: Plane,
-    quirks: &'a HashSet<Quirks>,
+    /// Whether the screen changed since the last `is_dirty` call.
+    dirty: Cell<bool>,
+    mode: ChipMode,
+    quirks: HashSet<Quirks>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Plane {
     First,
     Second,
     Both,
 }
 
+impl Plane {
+    /// The concrete planes the selection consists of, `First` and `Second`
+    /// for `Both`.
+    pub fn planes(&self) -> &'static [Plane] {
+        match self {
+            Plane::First => &[Plane::First],
+            Plane::Second => &[Plane::Second],
+            Plane::Both => &[Plane::First, Plane::Second],
+        }
+    }
+}
+
 pub enum ScreenResolution {
     Lores,
     Hires,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// The discriminant is the position of the color in a 4-color palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
 pub enum Color {
-    Disabled,
-    OnlyFirstPlane,
-    OnlySecondPlane,
-    Both,
+    Disabled = 0,
+    OnlyFirstPlane = 1,
+    OnlySecondPlane = 2,
+    Both = 3,
+}
+
+impl Color {
+    /// Position of the color in a 4-color palette, from `Disabled` to
+    /// `Both`.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
 }
 
-impl<'a> Display<'a> {
+impl Display {
     pub const WIDTH: usize = 64;
     pub const HEIGHT: usize = 32;
 
     pub const HIRES_WIDTH: usize = 128;
     pub const HIRES_HEIGHT: usize = 64;
 
-    pub fn new(quirks: &'a HashSet<Quirks>) -> Self {
+    pub const HIRES_64X64_HEIGHT: usize = 64;
+
+    pub const MEGA_WIDTH: usize = 256;
+    pub const MEGA_HEIGHT: usize = 192;
+
+    pub fn new(mode: &ChipMode, quirks: &HashSet<Quirks>) -> Self {
+        let (width, height) = Self::max_resolution(mode);
         Display {
-            first_plane: [false; 8192],
-            second_plane: [false; 8192],
+            first_plane: vec![false; width * height],
+            second_plane: vec![false; width * height],
             is_hires: false,
+            is_hires_64x64: false,
+            is_mega: false,
             current_plane: Plane::First,
-            quirks,
+            dirty: Cell::new(true),
+            mode: *mode,
+            quirks: quirks.clone(),
         }
     }
 
@@ -54,11 +143,12 @@ impl<'a> Display<'a> {
         mut y: usize,
         sprite: &[u8],
         plane: Plane,
-    ) -> PixelErased {
-        let mut pixel_erased = false;
-        let screen_width = self.width();
-        let screen_height = self.height();
-        let wraps_instead_clipping = self.quirks.contains(&Quirks::WrapsInsteadClipping);
+    ) -> DrawOutcome {
+        let mut outcome = DrawOutcome::default();
+        let (screen_width, screen_height, scale) = self.sprite_grid();
+        let wraps_horizontally = self.wraps_horizontally();
+        let wraps_vertically = self.wraps_vertically();
+        self.dirty.set(true);
         let plane_map = match plane {
             Plane::First => &mut self.first_plane,
             Plane::Second => &mut self.second_plane,
@@ -67,40 +157,73 @@ impl<'a> Display<'a> {
         x %= screen_width;
         y %= screen_height;
 
-        for row in 0..sprite.len() {
+        for (row, sprite_row) in sprite.iter().enumerate() {
             let mut y_cord = y + row;
 
             if y_cord >= screen_height {
-                if wraps_instead_clipping {
-                    y_cord = y_cord - screen_height;
+                if wraps_vertically {
+                    y_cord -= screen_height;
                 } else {
+                    outcome.clipped_rows = (sprite.len() - row) as u8;
                     break;
                 }
             }
 
+            let mut is_row_erased = false;
             for col in 0..8 {
                 let mut x_cord = x + col;
 
                 if x_cord >= screen_width {
-                    if wraps_instead_clipping {
-                        x_cord = x_cord - screen_width;
+                    if wraps_horizontally {
+                        x_cord -= screen_width;
                     } else {
                         break;
                     }
                 }
 
-                let coord = x_cord + y_cord * screen_width;
-                let is_current_pixel_set = plane_map[coord];
-                let is_new_pixel_set = ((sprite[row] >> (7 - col)) & 1) == 1;
-                plane_map[coord] ^= is_new_pixel_set;
-
-                if !pixel_erased && is_current_pixel_set && is_new_pixel_set {
-                    pixel_erased = true;
+                let is_new_pixel_set = ((sprite_row >> (7 - col)) & 1) == 1;
+                if is_new_pixel_set
+                    && Self::flip_pixel(plane_map, x_cord, y_cord, screen_width, scale)
+                {
+                    is_row_erased = true;
                 }
             }
+
+            if is_row_erased {
+                outcome.pixel_erased = true;
+                outcome.erased_rows += 1;
+            }
         }
 
-        pixel_erased
+        outcome
+    }
+
+    /// Same as `draw_sprite`, but draws on every plane of `plane`. `sprites`
+    /// holds a sprite per plane one after another, as XO-CHIP stores them,
+    /// so with both planes selected the second half goes to the second
+    /// plane.
+    ///
+    /// Returns the merged outcome and the planes a pixel was erased on.
+    pub fn draw_sprite_detailed(
+        &mut self,
+        x: usize,
+        y: usize,
+        sprites: &[u8],
+        plane: Plane,
+    ) -> (DrawOutcome, PlaneCollisions) {
+        let planes = plane.planes();
+        let sprite_len = (sprites.len() / planes.len()).max(1);
+        let mut collisions = PlaneCollisions::default();
+        let outcome = planes
+            .iter()
+            .zip(sprites.chunks(sprite_len))
+            .map(|(&plane, sprite)| {
+                let outcome = self.draw_sprite(x, y, sprite, plane);
+                collisions.record(plane, outcome);
+                outcome
+            })
+            .fold(DrawOutcome::default(), DrawOutcome::merge);
+        (outcome, collisions)
     }
 
     pub fn draw_16_16_sprite(
@@ -109,11 +232,12 @@ impl<'a> Display<'a> {
         mut y: usize,
         sprite: [u16; 16],
         plane: Plane,
-    ) -> PixelErased {
-        let mut pixel_erased = false;
-        let screen_width = self.width();
-        let screen_height = self.height();
-        let wraps_instead_clipping = self.quirks.contains(&Quirks::WrapsInsteadClipping);
+    ) -> DrawOutcome {
+        let mut outcome = DrawOutcome::default();
+        let (screen_width, screen_height, scale) = self.sprite_grid();
+        let wraps_horizontally = self.wraps_horizontally();
+        let wraps_vertically = self.wraps_vertically();
+        self.dirty.set(true);
         let plane_map = match plane {
             Plane::First => &mut self.first_plane,
             Plane::Second => &mut self.second_plane,
@@ -122,92 +246,254 @@ impl<'a> Display<'a> {
         x %= screen_width;
         y %= screen_height;
 
-        for row in 0..16 {
+        for (row, sprite_row) in sprite.iter().enumerate() {
             let mut y_cord = y + row;
 
             if y_cord >= screen_height {
-                if wraps_instead_clipping {
-                    y_cord = y_cord - screen_height;
+                if wraps_vertically {
+                    y_cord -= screen_height;
                 } else {
+                    outcome.clipped_rows = (sprite.len() - row) as u8;
                     break;
                 }
             }
 
+            let mut is_row_erased = false;
             for col in 0..16 {
                 let mut x_cord = x + col;
 
                 if x_cord >= screen_width {
-                    if wraps_instead_clipping {
-                        x_cord = x_cord - screen_width;
+                    if wraps_horizontally {
+                        x_cord -= screen_width;
                     } else {
                         break;
                     }
                 }
 
-                let coord = x_cord + y_cord * screen_width;
-                let is_current_pixel_set = plane_map[coord];
-                let is_new_pixel_set = ((sprite[row] >> (15 - col)) & 1) == 1;
-                plane_map[coord] ^= is_new_pixel_set;
-
-                if !pixel_erased && is_current_pixel_set && is_new_pixel_set {
-                    pixel_erased = true;
+                let is_new_pixel_set = ((sprite_row >> (15 - col)) & 1) == 1;
+                if is_new_pixel_set
+                    && Self::flip_pixel(plane_map, x_cord, y_cord, screen_width, scale)
+                {
+                    is_row_erased = true;
                 }
             }
+
+            if is_row_erased {
+                outcome.pixel_erased = true;
+                outcome.erased_rows += 1;
+            }
+        }
+
+        outcome
+    }
+
+    /// Copies `bitmap`, `width` pixels per row, to the rectangle at (`x`,
+    /// `y`) on every plane of `plane`, e.g. to show a splash screen. Unlike
+    /// sprites the pixels are set as they are instead of XORed, and the
+    /// part outside of the screen is clipped.
+    pub fn blit(&mut self, x: usize, y: usize, bitmap: &[bool], width: usize, plane: Plane) {
+        if width == 0 {
+            return;
         }
+        let screen_width = self.width();
+        let screen_height = self.height();
+        self.dirty.set(true);
+        plane.planes().iter().for_each(|plane| {
+            let plane_map = match plane {
+                Plane::First => &mut self.first_plane,
+                Plane::Second => &mut self.second_plane,
+                Plane::Both => unreachable!("Both is made of the first and second planes."),
+            };
+            bitmap
+                .chunks(width)
+                .enumerate()
+                .filter(|(row, _)| y + row < screen_height)
+                .for_each(|(row, pixels)| {
+                    pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(col, _)| x + col < screen_width)
+                        .for_each(|(col, &pixel)| {
+                            plane_map[x + col + (y + row) * screen_width] = pixel;
+                        });
+                });
+        });
+    }
+
+    /// Width and height of the grid sprites are drawn on and how many
+    /// pixels of the screen a sprite pixel takes in each direction.
+    fn sprite_grid(&self) -> (usize, usize, usize) {
+        if self.draws_lores_on_hires() {
+            (Self::WIDTH, Self::HEIGHT, 2)
+        } else {
+            (self.width(), self.height(), 1)
+        }
+    }
+
+    /// Flips the `scale`x`scale` block of the sprite pixel at (`x`, `y`).
+    /// Returns whether any pixel of it was turned off.
+    fn flip_pixel(
+        plane_map: &mut [bool],
+        x: usize,
+        y: usize,
+        grid_width: usize,
+        scale: usize,
+    ) -> bool {
+        let screen_width = grid_width * scale;
+        let mut is_erased = false;
+        for block_y in y * scale..(y + 1) * scale {
+            for block_x in x * scale..(x + 1) * scale {
+                let coord = block_x + block_y * screen_width;
+                is_erased |= plane_map[coord];
+                plane_map[coord] ^= true;
+            }
+        }
+        is_erased
+    }
+
+    /// Whether the screen is always 128x64, see `Quirks::LoresWideSprites`.
+    fn has_only_hires_screen(&self) -> bool {
+        matches!(self.mode, ChipMode::SuperChip | ChipMode::MegaChip)
+            && self.quirks.contains(&Quirks::LoresWideSprites)
+    }
 
-        pixel_erased
+    /// Whether low resolution sprites are scaled up on the 128x64 screen.
+    fn draws_lores_on_hires(&self) -> bool {
+        self.has_only_hires_screen() && !self.is_hires && !self.is_mega
     }
 
+    /// Value of VF after drawing a sprite with this `outcome`.
+    ///
+    /// SUPER-CHIP in high resolution counts the rows that erased a pixel
+    /// plus the rows clipped at the bottom, the others only tell whether a
+    /// pixel was erased.
+    pub fn collision_flag(&self, outcome: DrawOutcome) -> u8 {
+        match self.mode {
+            ChipMode::SuperChip | ChipMode::MegaChip if self.is_hires => {
+                outcome.erased_rows + outcome.clipped_rows
+            }
+            _ => outcome.pixel_erased as u8,
+        }
+    }
+
+    /// Scrolls the selected planes `lines` rows down. The whole screen is
+    /// cleared when `lines` is not less than its height.
     pub fn scroll_n_lines_down(&mut self, lines: u8) {
         let width = self.width();
         let height = self.height();
-        let moved_part = lines as usize * width;
-        let remaining_part = width * (height - lines as usize);
-        self.get_selected_planes().into_iter().for_each(|plane| {
+        let lines = self.scroll_distance(lines as usize).min(height);
+        let moved_part = lines * width;
+        let remaining_part = width * (height - lines);
+        self.get_selected_planes().for_each(|plane| {
             plane.copy_within(..remaining_part, moved_part);
             plane[..moved_part].fill(false);
         });
     }
 
+    /// Scrolls the selected planes `lines` rows up. The whole screen is
+    /// cleared when `lines` is not less than its height.
     pub fn scroll_n_lines_up(&mut self, lines: u8) {
         let width = self.width();
         let height = self.height();
-        let moved_part = width * lines as usize;
-        let remaining_part = width * (height - lines as usize);
-        self.get_selected_planes().into_iter().for_each(|plane| {
+        let lines = self.scroll_distance(lines as usize).min(height);
+        let moved_part = width * lines;
+        let remaining_part = width * (height - lines);
+        self.get_selected_planes().for_each(|plane| {
             plane.copy_within(moved_part.., 0);
             plane[remaining_part..].fill(false);
         });
     }
 
     pub fn scroll_4_px_right(&mut self) {
+        self.scroll_px_right(self.scroll_distance(4));
+    }
+
+    pub fn scroll_4_px_left(&mut self) {
+        self.scroll_px_left(self.scroll_distance(4));
+    }
+
+    /// Scrolls the selected planes `px` pixels right. The whole screen is
+    /// cleared when `px` is not less than its width.
+    pub fn scroll_px_right(&mut self, px: usize) {
         let width = self.width();
         let height = self.height();
-        self.get_selected_planes().into_iter().for_each(|plane| {
-            (0..height).into_iter().for_each(|row| {
-                plane.copy_within(row * width..(row + 1) * width - 4, row * width + 4);
-                plane[row * width..row * width + 4].copy_from_slice(&[false; 4]);
+        let px = px.min(width);
+        self.get_selected_planes().for_each(|plane| {
+            (0..height).for_each(|row| {
+                plane.copy_within(row * width..(row + 1) * width - px, row * width + px);
+                plane[row * width..row * width + px].fill(false);
             });
         });
     }
 
-    pub fn scroll_4_px_left(&mut self) {
+    /// Scrolls the selected planes `px` pixels left. The whole screen is
+    /// cleared when `px` is not less than its width.
+    pub fn scroll_px_left(&mut self, px: usize) {
         let width = self.width();
         let height = self.height();
-        self.get_selected_planes().into_iter().for_each(|plane| {
-            (0..height).into_iter().for_each(|row| {
-                plane.copy_within(row * width + 4..(row + 1) * width, row * width);
-                plane[row * width + width - 4..(row + 1) * width].copy_from_slice(&[false; 4]);
+        let px = px.min(width);
+        self.get_selected_planes().for_each(|plane| {
+            (0..height).for_each(|row| {
+                plane.copy_within(row * width + px..(row + 1) * width, row * width);
+                plane[(row + 1) * width - px..(row + 1) * width].fill(false);
             });
         });
     }
 
+    fn wraps_horizontally(&self) -> bool {
+        self.quirks.contains(&Quirks::WrapsInsteadClipping)
+            || self.quirks.contains(&Quirks::WrapsHorizontally)
+    }
+
+    fn wraps_vertically(&self) -> bool {
+        self.quirks.contains(&Quirks::WrapsInsteadClipping)
+            || self.quirks.contains(&Quirks::WrapsVertically)
+    }
+
+    /// Scroll distance in pixels of the screen.
+    ///
+    /// With `LoresHalfScroll` quirk the SUPER-CHIP scroll distance is halved
+    /// in low resolution. When low resolution is drawn on the high
+    /// resolution screen, a low resolution pixel is 2 pixels of it. XO-CHIP
+    /// always scrolls by whole low resolution pixels.
+    fn scroll_distance(&self, distance: usize) -> usize {
+        let is_half_scroll = matches!(self.mode, ChipMode::SuperChip | ChipMode::MegaChip)
+            && !self.is_hires
+            && self.quirks.contains(&Quirks::LoresHalfScroll);
+        match (self.draws_lores_on_hires(), is_half_scroll) {
+            (true, true) => distance,
+            (true, false) => distance * 2,
+            (false, true) => distance / 2,
+            (false, false) => distance,
+        }
+    }
+
+    /// Clears the selected planes.
     pub fn clear(&mut self) {
-        self.get_selected_planes().into_iter().for_each(|plane| {
+        self.get_selected_planes().for_each(|plane| {
             plane.fill(false);
         });
     }
 
+    /// Clears both planes no matter which are selected.
+    pub fn clear_all(&mut self) {
+        self.first_plane.fill(false);
+        self.second_plane.fill(false);
+        self.dirty.set(true);
+    }
+
+    /// Clears both planes and brings back the low resolution and the
+    /// first plane selection.
+    pub fn reset(&mut self) {
+        self.first_plane.fill(false);
+        self.second_plane.fill(false);
+        self.is_hires = false;
+        self.is_hires_64x64 = false;
+        self.is_mega = false;
+        self.current_plane = Plane::First;
+        self.dirty.set(true);
+    }
+
     pub fn set_plane(&mut self, plane: Plane) {
         self.current_plane = plane;
     }
@@ -216,47 +502,204 @@ impl<'a> Display<'a> {
         &self.current_plane
     }
 
-    pub fn display_bitplane(&self) -> [Color; 8192] {
-        self.first_plane
+    /// The monochrome framebuffer of the first plane, one entry per pixel
+    /// row by row. Its size is `width() * height()`, so it holds 64x32
+    /// pixels in low resolution, 64x64 pixels in the hires CHIP-8 mode,
+    /// 128x64 pixels in high resolution and 256x192 in the MEGA-CHIP mode.
+    ///
+    /// Use `display_bitplane` to render both planes in color.
+    pub fn buffer(&self) -> &[bool] {
+        &self.first_plane[..self.width() * self.height()]
+    }
+
+    /// Whether the pixel at (`x`, `y`) is set on `plane`. For `Plane::Both`
+    /// it must be set on both planes.
+    pub fn pixel(&self, x: usize, y: usize, plane: Plane) -> bool {
+        let coord = self.coord(x, y);
+        match plane {
+            Plane::First => self.first_plane[coord],
+            Plane::Second => self.second_plane[coord],
+            Plane::Both => self.first_plane[coord] && self.second_plane[coord],
+        }
+    }
+
+    /// The color of the pixel at (`x`, `y`), without building the whole
+    /// `display_bitplane`.
+    pub fn color_at(&self, x: usize, y: usize) -> Color {
+        let coord = self.coord(x, y);
+        match (self.first_plane[coord], self.second_plane[coord]) {
+            (false, false) => Color::Disabled,
+            (true, false) => Color::OnlyFirstPlane,
+            (false, true) => Color::OnlySecondPlane,
+            (true, true) => Color::Both,
+        }
+    }
+
+    /// The color of every pixel, `width() * height()` of them row by row.
+    pub fn display_bitplane(&self) -> Vec<Color> {
+        let mut bitplane = vec![Color::Disabled; self.width() * self.height()];
+        self.write_bitplane(&mut bitplane);
+        bitplane
+    }
+
+    /// Same as `display_bitplane`, but fills `out`, so a renderer can reuse
+    /// it every frame instead of getting a new vector. Pixels past its end
+    /// are skipped.
+    pub fn write_bitplane(&self, out: &mut [Color]) {
+        let len = self.width() * self.height();
+        self.first_plane[..len]
             .iter()
-            .zip(self.second_plane.iter())
-            .map(|(first_plane_pixel, second_plane_pixel)| {
-                match (first_plane_pixel, second_plane_pixel) {
+            .zip(self.second_plane[..len].iter())
+            .zip(out.iter_mut())
+            .for_each(|((first_plane_pixel, second_plane_pixel), color)| {
+                *color = match (first_plane_pixel, second_plane_pixel) {
                     (false, false) => Color::Disabled,
                     (true, false) => Color::OnlyFirstPlane,
                     (false, true) => Color::OnlySecondPlane,
                     (true, true) => Color::Both,
                 }
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+            });
+    }
+
+    /// The `Color::index` of every pixel, `width() * height()` bytes row by
+    /// row: bit 0 is the first plane and bit 1 the second one. Unlike
+    /// `display_bitplane` it has no palette applied, e.g. for lossless frame
+    /// dumps compared with other emulators.
+    pub fn index_buffer(&self) -> Vec<u8> {
+        let mut out = vec![0; self.width() * self.height()];
+        self.write_index_buffer(&mut out);
+        out
+    }
+
+    /// Same as `index_buffer`, but fills `out`. Pixels past its end are
+    /// skipped.
+    pub fn write_index_buffer(&self, out: &mut [u8]) {
+        let len = self.width() * self.height();
+        self.first_plane[..len]
+            .iter()
+            .zip(self.second_plane[..len].iter())
+            .zip(out.iter_mut())
+            .for_each(|((&first_plane_pixel, &second_plane_pixel), index)| {
+                *index = first_plane_pixel as u8 | (second_plane_pixel as u8) << 1;
+            });
     }
 
     pub fn enable_hires(&mut self) {
-        self.clear();
+        if self.quirks.contains(&Quirks::ClearOnModeSwitch) {
+            self.clear();
+        }
         self.is_hires = true;
+        self.dirty.set(true);
     }
 
     pub fn disable_hires(&mut self) {
-        self.clear();
+        if self.quirks.contains(&Quirks::ClearOnModeSwitch) {
+            self.clear();
+        }
         self.is_hires = false;
+        self.dirty.set(true);
     }
 
     pub fn is_hires(&self) -> bool {
         self.is_hires
     }
 
-    fn get_selected_planes(&mut self) -> Vec<&mut [bool; 8192]> {
-        match self.current_plane {
-            Plane::First => vec![&mut self.first_plane],
-            Plane::Second => vec![&mut self.second_plane],
-            Plane::Both => vec![&mut self.first_plane, &mut self.second_plane],
+    /// Switches to the 64x64 mode of the hires CHIP-8 and clears the
+    /// screen. Only the height changes, so sprites are drawn the same way
+    /// as in low resolution.
+    pub fn enable_hires_64x64(&mut self) {
+        self.first_plane.fill(false);
+        self.second_plane.fill(false);
+        self.is_hires_64x64 = true;
+        self.dirty.set(true);
+    }
+
+    pub fn is_hires_64x64(&self) -> bool {
+        self.is_hires_64x64
+    }
+
+    /// Switches MEGA-CHIP to its 256x192 screen.
+    pub fn enable_mega(&mut self) {
+        if self.quirks.contains(&Quirks::ClearOnModeSwitch) {
+            self.clear();
         }
+        self.is_mega = true;
+        self.dirty.set(true);
     }
 
+    /// Goes back to the SUPER-CHIP screen.
+    pub fn disable_mega(&mut self) {
+        if self.quirks.contains(&Quirks::ClearOnModeSwitch) {
+            self.clear();
+        }
+        self.is_mega = false;
+        self.dirty.set(true);
+    }
+
+    pub fn is_mega(&self) -> bool {
+        self.is_mega
+    }
+
+    /// Whether the screen was drawn on, scrolled, cleared or changed its
+    /// resolution since the last call, so a renderer can skip static
+    /// frames. It is true before the first call.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.replace(false)
+    }
+
+    pub fn planes(&self) -> (&[bool], &[bool]) {
+        (&self.first_plane, &self.second_plane)
+    }
+
+    /// Replaces the content of both planes and the resolution without
+    /// clearing the screen.
+    pub fn load_planes(
+        &mut self,
+        first_plane: &[bool],
+        second_plane: &[bool],
+        is_hires: bool,
+        is_hires_64x64: bool,
+        is_mega: bool,
+    ) {
+        let first_len = first_plane.len().min(self.first_plane.len());
+        let second_len = second_plane.len().min(self.second_plane.len());
+        self.first_plane[..first_len].copy_from_slice(&first_plane[..first_len]);
+        self.second_plane[..second_len].copy_from_slice(&second_plane[..second_len]);
+        self.is_hires = is_hires;
+        self.is_hires_64x64 = is_hires_64x64;
+        self.is_mega = is_mega;
+        self.dirty.set(true);
+    }
+
+    fn get_selected_planes(&mut self) -> impl Iterator<Item = &mut [bool]> {
+        self.dirty.set(true);
+        let (first, second) = match self.current_plane {
+            Plane::First => (Some(self.first_plane.as_mut_slice()), None),
+            Plane::Second => (None, Some(self.second_plane.as_mut_slice())),
+            Plane::Both => (
+                Some(self.first_plane.as_mut_slice()),
+                Some(self.second_plane.as_mut_slice()),
+            ),
+        };
+        first.into_iter().chain(second)
+    }
+
+    fn coord(&self, x: usize, y: usize) -> usize {
+        let width = self.width();
+        let height = self.height();
+        assert!(
+            x < width && y < height,
+            "Pixel ({x}, {y}) is out of the {width}x{height} screen"
+        );
+        x + y * width
+    }
+
+    /// Width of the screen. With `Quirks::LoresWideSprites` SUPER-CHIP is
+    /// always 128x64 and low resolution sprites are scaled up on it.
     pub fn width(&self) -> usize {
-        if self.is_hires {
+        if self.is_mega {
+            Self::MEGA_WIDTH
+        } else if self.is_hires || self.has_only_hires_screen() {
             Self::HIRES_WIDTH
         } else {
             Self::WIDTH
@@ -264,10 +707,144 @@ impl<'a> Display<'a> {
     }
 
     pub fn height(&self) -> usize {
-        if self.is_hires {
+        if self.is_mega {
+            Self::MEGA_HEIGHT
+        } else if self.is_hires || self.has_only_hires_screen() {
             Self::HIRES_HEIGHT
+        } else if self.is_hires_64x64 {
+            Self::HIRES_64X64_HEIGHT
         } else {
             Self::HEIGHT
         }
     }
+
+    /// Current width and height of the screen, e.g. for sizing a window.
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.width(), self.height())
+    }
+
+    /// The largest screen `mode` can switch to: 256x192 for MEGA-CHIP and
+    /// 128x64 for the others.
+    pub fn max_resolution(mode: &ChipMode) -> (usize, usize) {
+        match mode {
+            ChipMode::MegaChip => (Self::MEGA_WIDTH, Self::MEGA_HEIGHT),
+            _ => (Self::HIRES_WIDTH, Self::HIRES_HEIGHT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coordinates of the set pixels of the first plane, row by row.
+    fn lit_pixels(display: &Display) -> Vec<(usize, usize)> {
+        let width = display.width();
+        (0..width * display.height())
+            .filter(|&pixel| display.buffer()[pixel])
+            .map(|pixel| (pixel % width, pixel / width))
+            .collect()
+    }
+
+    #[test]
+    fn scroll_by_zero_px_keeps_the_screen() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::SuperChip, &quirks);
+        display.draw_sprite(10, 5, &[0xC0], Plane::First);
+
+        display.scroll_px_right(0);
+        display.scroll_px_left(0);
+        assert_eq!(lit_pixels(&display), [(10, 5), (11, 5)]);
+    }
+
+    #[test]
+    fn scroll_right_by_width_minus_one_px() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::SuperChip, &quirks);
+        display.draw_sprite(0, 5, &[0xC0], Plane::First);
+
+        display.scroll_px_right(Display::WIDTH - 1);
+        assert_eq!(lit_pixels(&display), [(Display::WIDTH - 1, 5)]);
+    }
+
+    #[test]
+    fn scroll_left_by_width_minus_one_px() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::SuperChip, &quirks);
+        display.draw_sprite(Display::WIDTH - 2, 5, &[0xC0], Plane::First);
+
+        display.scroll_px_left(Display::WIDTH - 1);
+        assert_eq!(lit_pixels(&display), [(0, 5)]);
+    }
+
+    #[test]
+    fn scroll_by_width_or_more_clears_the_screen() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::SuperChip, &quirks);
+        display.draw_sprite(30, 5, &[0xFF], Plane::First);
+
+        display.scroll_px_right(Display::WIDTH);
+        assert!(lit_pixels(&display).is_empty());
+        display.draw_sprite(30, 5, &[0xFF], Plane::First);
+        display.scroll_px_left(Display::WIDTH + 10);
+        assert!(lit_pixels(&display).is_empty());
+    }
+
+    #[test]
+    fn sprite_is_clipped_at_the_edges() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::Chip8, &quirks);
+
+        let outcome = display.draw_sprite(62, 30, &[0xFF; 4], Plane::First);
+        assert_eq!(
+            lit_pixels(&display),
+            [(62, 30), (63, 30), (62, 31), (63, 31)]
+        );
+        assert_eq!(outcome.clipped_rows, 2);
+    }
+
+    #[test]
+    fn sprite_wraps_around_the_edges_with_quirk() {
+        let quirks = HashSet::from([Quirks::WrapsInsteadClipping]);
+        let mut display = Display::new(&ChipMode::Chip8, &quirks);
+
+        let outcome = display.draw_sprite(63, 31, &[0xC0; 2], Plane::First);
+        assert_eq!(lit_pixels(&display), [(0, 0), (63, 0), (0, 31), (63, 31)]);
+        assert_eq!(outcome.clipped_rows, 0);
+    }
+
+    #[test]
+    fn sprite_position_wraps_around_the_screen() {
+        let quirks = HashSet::new();
+        let mut display = Display::new(&ChipMode::Chip8, &quirks);
+
+        display.draw_sprite(
+            Display::WIDTH + 2,
+            Display::HEIGHT + 3,
+            &[0x80],
+            Plane::First,
+        );
+        assert_eq!(lit_pixels(&display), [(2, 3)]);
+    }
+
+    #[test]
+    fn draw_sprite_detailed_draws_a_sprite_per_plane() {
+        let mode = ChipMode::XOChip;
+        let quirks = HashSet::new();
+        let mut display = Display::new(&mode, &quirks);
+        display.draw_sprite(0, 0, &[0x40], Plane::Second);
+
+        let (outcome, collisions) = display.draw_sprite_detailed(0, 0, &[0x80, 0xC0], Plane::Both);
+        assert!(outcome.pixel_erased);
+        assert_eq!(
+            collisions,
+            PlaneCollisions {
+                first_plane: false,
+                second_plane: true,
+            }
+        );
+        assert!(display.pixel(0, 0, Plane::First));
+        assert!(display.pixel(0, 0, Plane::Second));
+        assert!(!display.pixel(1, 0, Plane::Second));
+    }
 }
@@ -1,3 +1,5 @@
+use std::fmt::{Debug, Display};
+
 /// Represents an CHIP-8 instruction, for instance:
 /// * 00E0 - CLS (Clear the display)
 /// * 00EE - RET (Return from a subroutine)
@@ -9,7 +11,6 @@
 ///      |        |
 ///    ++++ ++++++++++++++
 ///   `0000_0000_1110_0000`
-#[derive(Debug)]
 pub struct Instruction {
     /// The initial form of received instruction.
     value: u16,
@@ -57,3 +58,24 @@ impl Instruction {
         self.value as u8
     }
 }
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:04X}", self.value)
+    }
+}
+
+impl Debug for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (first_nibble, second_nibble, third_nibble, fourth_nibble) = self.nibbles();
+        f.debug_struct("Instruction")
+            .field("value", &format_args!("0x{:04X}", self.value))
+            .field(
+                "nibbles",
+                &format_args!(
+                    "({first_nibble:X}, {second_nibble:X}, {third_nibble:X}, {fourth_nibble:X})"
+                ),
+            )
+            .finish()
+    }
+}
@@ -1,19 +1,67 @@
-#[derive(Default)]
+use std::collections::VecDeque;
+
+/// The hexadecimal keypad. Keys are stored by their CHIP-8 value, so
+/// `keys[0xA]` is the state of the `A` key.
 pub struct Keyboard {
     keys: [bool; 16],
+    /// Keys in the order they went down, kept even after they are
+    /// released, so a tap shorter than a frame isn't missed by `Fx0A`.
+    presses: VecDeque<u8>,
+    /// Maps a raw keypad index to the CHIP-8 key it produces.
+    layout: [u8; 16],
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Keyboard {
+            keys: [false; 16],
+            presses: VecDeque::new(),
+            layout: std::array::from_fn(|key| key as u8),
+        }
+    }
 }
 
 impl Keyboard {
+    /// At most this many presses are remembered, the oldest are dropped.
+    const MAX_PRESSES: usize = 16;
+
+    /// Remaps the raw keypad, `layout[i]` is the CHIP-8 key produced by
+    /// pressing the raw key `i`. By default every raw key produces itself.
+    pub fn set_layout(&mut self, layout: [u8; 16]) {
+        self.layout = layout.map(|key| key & 0xF);
+    }
+
+    /// Presses the raw key `key`, which is translated with the layout.
     pub fn press_key(&mut self, key: u8) {
+        let key = self.layout[key as usize];
+        if !self.keys[key as usize] {
+            if self.presses.len() == Self::MAX_PRESSES {
+                self.presses.pop_front();
+            }
+            self.presses.push_back(key);
+        }
         self.keys[key as usize] = true;
     }
 
+    /// Releases the raw key `key`, which is translated with the layout.
     pub fn release_key(&mut self, key: u8) {
-        self.keys[key as usize] = false;
+        self.keys[self.layout[key as usize] as usize] = false;
     }
 
     pub fn is_key_pressed(&self, key: u8) -> bool {
-        *self.keys.get(key as usize).or(Some(&false)).unwrap()
+        *self.keys.get(key as usize).unwrap_or(&false)
+    }
+
+    /// Every key that is down at the moment, in ascending order.
+    pub fn pressed_keys(&self) -> impl Iterator<Item = u8> + '_ {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &key)| if key { Some(i as u8) } else { None })
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        self.keys.contains(&true)
     }
 
     pub fn pressed_key(&self) -> Option<u8> {
@@ -22,4 +70,15 @@ impl Keyboard {
             .enumerate()
             .find_map(|(i, &key)| if key { Some(i as u8) } else { None })
     }
+
+    /// Takes the oldest press that wasn't taken yet, the key may be
+    /// released already.
+    pub fn take_press(&mut self) -> Option<u8> {
+        self.presses.pop_front()
+    }
+
+    /// Forgets the presses that weren't taken.
+    pub fn clear_presses(&mut self) {
+        self.presses.clear();
+    }
 }
@@ -1,9 +1,17 @@
+pub mod builder;
 pub mod chip;
+pub mod debug;
+pub mod disasm;
 pub mod display;
-mod instruction;
+pub mod instruction;
 pub mod keyboard;
-mod memory;
+pub mod memory;
+pub mod op;
 pub mod platform;
 mod registers;
 pub mod rom;
 mod stack;
+pub mod state;
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
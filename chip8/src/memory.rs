@@ -1,5 +1,10 @@
+use crate::debug::WatchKind;
 use crate::display::ScreenResolution;
 use crate::platform::ChipMode;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::{Bound, RangeBounds};
 
 // http://devernay.free.fr/hacks/chip8/C8TECH10.HTM#2.1
 //
@@ -26,90 +31,223 @@ use crate::platform::ChipMode;
 // | Reserved for  |
 // |  interpreter  |
 // +---------------+= 0x000 (0) Start of Chip-8 RAM
-pub struct Memory<'a> {
-    map: [u8; Memory::EXTENDED_MEMORY_SIZE as usize],
-    mode: &'a ChipMode,
+pub struct Memory {
+    map: Vec<u8>,
+    mode: ChipMode,
     rpl_flags: [u8; 16],
+    /// The last addressable byte, which is also used as a mask for
+    /// addresses.
     memory_size: u16,
+    /// The small font, loaded at `font_address` and followed by the
+    /// large one on SUPER-CHIP and XO-CHIP.
+    font: [u8; 80],
+    font_address: u16,
+    /// Where the program is loaded and starts.
+    load_address: u16,
+    watchpoints: HashMap<u16, WatchKind>,
+    /// The first watched access since the last `take_watch_hit` call.
+    /// It is a `Cell` so reads work through a shared reference.
+    watch_hit: Cell<Option<(u16, WatchKind)>>,
 }
 
-impl<'a> Memory<'a> {
+#[derive(Debug)]
+pub enum MemoryError {
+    /// The program has no instructions.
+    EmptyProgram,
+    /// The program can't be loaded at this address, it is either in the
+    /// interpreter area or past the end of the memory.
+    InvalidLoadAddress(u16),
+    /// The program of this size doesn't fit between the load address and
+    /// the end of the memory.
+    ProgramTooLarge { size: usize, max_size: usize },
+    /// `len` bytes loaded at `addr` don't fit into the memory.
+    LoadOutOfBounds { addr: u16, len: usize },
+    /// The program attempted to write to the interpreter area at this
+    /// address.
+    WriteToReserved(u16),
+    /// The address is past the end of the memory.
+    OutOfBounds(u16),
+    /// The small and large fonts loaded at this address don't fit into the
+    /// interpreter area.
+    FontOutOfBounds(u16),
+}
+
+impl Memory {
     const RESERVED_ADDR_START: u16 = 0;
     pub const PROGRAM_ADDR_START: u16 = 0x200;
+    pub const ETI_660_PROGRAM_ADDR_START: u16 = 0x600;
+    /// Size of the 8x10 font SUPER-CHIP and XO-CHIP load after the small one.
+    const LARGE_FONT_LEN: usize = 16 * 10;
     const MEMORY_SIZE: u16 = 0x0FFF;
     const EXTENDED_MEMORY_SIZE: u16 = 0xFFFF;
 
-    pub fn new(program: &[u8], mode: &'a ChipMode) -> Memory<'a> {
+    /// The 4x5 hexadecimal font, each digit takes 5 bytes.
+    pub const DEFAULT_FONT: [u8; 80] = [
+        0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+        0x20, 0x60, 0x20, 0x20, 0x70, // 1
+        0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+        0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+        0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+        0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+        0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+        0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+        0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+        0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+        0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+        0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+        0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+        0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+        0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+        0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+    ];
+
+    /// Allocates 4KB of memory for CHIP-8 and SUPER-CHIP and 64KB for
+    /// XO-CHIP and loads `program` into it.
+    pub fn new(program: &[u8], mode: &ChipMode) -> Result<Memory, MemoryError> {
+        Self::with_font(program, mode, Self::DEFAULT_FONT, 0)
+    }
+
+    /// Same as `new`, but loads `font` instead of the default small font
+    /// starting at `font_address`, e.g. 0x050.
+    pub fn with_font(
+        program: &[u8],
+        mode: &ChipMode,
+        font: [u8; 80],
+        font_address: u16,
+    ) -> Result<Memory, MemoryError> {
+        Self::with_layout(program, mode, font, font_address, Self::PROGRAM_ADDR_START)
+    }
+
+    /// Same as `new`, but loads `program` at `load_address`, e.g. 0x600 for
+    /// ETI 660 programs. It must be in the program space.
+    pub fn with_load_address(
+        program: &[u8],
+        mode: &ChipMode,
+        load_address: u16,
+    ) -> Result<Memory, MemoryError> {
+        Self::with_layout(program, mode, Self::DEFAULT_FONT, 0, load_address)
+    }
+
+    /// Combines `with_font` and `with_load_address`. Fails when the fonts
+    /// at `font_address` run into the program space.
+    pub fn with_layout(
+        program: &[u8],
+        mode: &ChipMode,
+        font: [u8; 80],
+        font_address: u16,
+        load_address: u16,
+    ) -> Result<Memory, MemoryError> {
+        if font_address as usize + font.len() + Self::LARGE_FONT_LEN
+            > Self::PROGRAM_ADDR_START as usize
+        {
+            return Err(MemoryError::FontOutOfBounds(font_address));
+        }
+        let memory_size = match mode {
+            ChipMode::XOChip | ChipMode::MegaChip => Self::EXTENDED_MEMORY_SIZE,
+            _ => Self::MEMORY_SIZE,
+        };
+        if !(Self::PROGRAM_ADDR_START..=memory_size).contains(&load_address) {
+            return Err(MemoryError::InvalidLoadAddress(load_address));
+        }
+        let max_size = memory_size as usize + 1 - load_address as usize;
+        if program.is_empty() {
+            return Err(MemoryError::EmptyProgram);
+        }
+        if program.len() > max_size {
+            return Err(MemoryError::ProgramTooLarge {
+                size: program.len(),
+                max_size,
+            });
+        }
+
         let mut memory = Memory {
-            map: [0; Memory::EXTENDED_MEMORY_SIZE as usize],
+            map: vec![0; memory_size as usize + 1],
             rpl_flags: [0; 16],
-            memory_size: match mode {
-                ChipMode::XOChip => Self::EXTENDED_MEMORY_SIZE,
-                _ => Self::MEMORY_SIZE,
-            },
-            mode,
+            memory_size,
+            mode: *mode,
+            font,
+            font_address,
+            load_address,
+            watchpoints: HashMap::new(),
+            watch_hit: Cell::new(None),
         };
+        memory.reset(program);
+        Ok(memory)
+    }
 
-        memory.load_font_sprites();
+    /// Brings the memory back to its initial state with `program` loaded,
+    /// reusing the allocated space. `program` must fit into the memory, as
+    /// checked by `new`.
+    pub fn reset(&mut self, program: &[u8]) {
+        self.map.fill(0);
+        self.rpl_flags.fill(0);
+        self.watch_hit.set(None);
+        self.load_font_sprites();
 
         program.iter().enumerate().for_each(|(i, &byte)| {
-            memory.map[Self::PROGRAM_ADDR_START as usize + i] = byte;
+            self.map[self.load_address as usize + i] = byte;
         });
-
-        memory
     }
 
-    pub fn write(&mut self, addr: u16, val: u8) {
+    pub fn write(&mut self, addr: u16, val: u8) -> Result<(), MemoryError> {
         match addr {
             Memory::RESERVED_ADDR_START..Memory::PROGRAM_ADDR_START => {
-                panic!(
-                    "Attempted to write to CHIP-8 interpreter address space: {:04x}",
-                    addr
-                );
+                Err(MemoryError::WriteToReserved(addr))
             }
             Memory::PROGRAM_ADDR_START..=Memory::EXTENDED_MEMORY_SIZE
                 if addr <= self.memory_size =>
             {
-                self.map[addr as usize] = val
+                self.check_watchpoint(addr, WatchKind::Write);
+                self.map[addr as usize] = val;
+                Ok(())
             }
-            _ => panic!(
-                "Attempted to write to the out-of-bound address: {:04x}",
-                addr
-            ),
+            _ => Err(MemoryError::OutOfBounds(addr)),
         }
     }
 
-    pub fn read(&mut self, addr: u16) -> u8 {
+    pub fn read(&self, addr: u16) -> Result<u8, MemoryError> {
         if addr > self.memory_size {
-            panic!("Attempted to read out-of-bound address: {:04x}", addr);
+            return Err(MemoryError::OutOfBounds(addr));
         }
-        self.map[addr as usize]
+        self.check_watchpoint(addr, WatchKind::Read);
+        Ok(self.map[addr as usize])
     }
 
-    pub fn read_n_bytes(&mut self, addr: u16, n: u16) -> Vec<u8> {
+    /// Reads the 2-byte opcode at `addr`. Instruction fetches don't
+    /// trigger watchpoints. `None` if the opcode doesn't fit into the
+    /// memory.
+    pub fn fetch(&self, addr: u16) -> Option<u16> {
+        if addr >= self.memory_size {
+            return None;
+        }
+        Some(u16::from_be_bytes([
+            self.map[addr as usize],
+            self.map[addr as usize + 1],
+        ]))
+    }
+
+    pub fn read_n_bytes(&self, addr: u16, n: u16) -> Result<Vec<u8>, MemoryError> {
         (0..n)
-            .into_iter()
             .map(|i| self.read(addr.wrapping_add(i)))
-            .collect::<Vec<u8>>()
+            .collect::<Result<Vec<u8>, _>>()
     }
 
-    pub fn read_n_2bytes(&mut self, addr: u16, n: u16) -> Vec<u16> {
-        (0..2 * n)
-            .into_iter()
-            .map(|i| self.read(addr.wrapping_add(i)))
-            .collect::<Vec<u8>>()
+    pub fn read_n_2bytes(&self, addr: u16, n: u16) -> Result<Vec<u16>, MemoryError> {
+        Ok(self
+            .read_n_bytes(addr, 2 * n)?
             .chunks_exact(2)
             .map(|sprite_bytes| u16::from_be_bytes(sprite_bytes.try_into().unwrap()))
-            .collect::<Vec<u16>>()
+            .collect::<Vec<u16>>())
     }
 
+    /// Address of the small (`Lores`) or large (`Hires`) font sprite of
+    /// `digit`. Only its low nibble is used, so every byte has a sprite. The
+    /// large font is only loaded on SUPER-CHIP and later.
     pub fn get_font_address(&self, digit: u8, resolution: ScreenResolution) -> u16 {
-        match (self.mode, resolution, digit) {
-            (_, ScreenResolution::Lores, _) if digit <= 0xF => (digit * 5) as u16,
-            (ChipMode::SuperChip | ChipMode::XOChip, ScreenResolution::Hires, _) => {
-                (16 * 5 + digit * 10) as u16
-            }
-            _ => panic!("Invalid font sprite {digit} for mode {}", self.mode),
+        let digit = (digit & 0xF) as u16;
+        match resolution {
+            ScreenResolution::Lores => self.font_address + digit * 5,
+            ScreenResolution::Hires => self.font_address + self.font.len() as u16 + digit * 10,
         }
     }
 
@@ -119,37 +257,99 @@ impl<'a> Memory<'a> {
         });
     }
 
-    pub fn read_rpl_flags(&mut self) -> &[u8] {
+    pub fn read_rpl_flags(&self) -> &[u8] {
         &self.rpl_flags
     }
 
+    pub fn map(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Copies the bytes in `range`, e.g. `0x200..0x300` or `..` for the
+    /// whole memory. Unlike `read` it never triggers watchpoints.
+    ///
+    /// The range is clamped to the memory, so the part past its end is
+    /// left out and an empty or reversed range gives no bytes.
+    pub fn dump(&self, range: impl RangeBounds<u16>) -> Vec<u8> {
+        let start = match range.start_bound() {
+            Bound::Included(&addr) => addr as usize,
+            Bound::Excluded(&addr) => addr as usize + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&addr) => addr as usize + 1,
+            Bound::Excluded(&addr) => addr as usize,
+            Bound::Unbounded => self.map.len(),
+        };
+        let end = end.min(self.map.len());
+        self.map[start.min(end)..end].to_vec()
+    }
+
+    /// Copies `data` to the memory starting at `addr`. The interpreter area
+    /// can be written too, as it is the host and not the program writing.
+    pub fn load_bytes_at(&mut self, addr: u16, data: &[u8]) -> Result<(), MemoryError> {
+        let start = addr as usize;
+        let end = start + data.len();
+        if end > self.memory_size as usize + 1 {
+            return Err(MemoryError::LoadOutOfBounds {
+                addr,
+                len: data.len(),
+            });
+        }
+        self.map[start..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Overwrites the memory with `map`, interpreter area included. A
+    /// shorter `map` leaves the rest of the memory as it is, a longer one
+    /// is rejected and nothing is loaded.
+    pub fn load_map(&mut self, map: &[u8]) -> Result<(), MemoryError> {
+        if map.len() > self.map.len() {
+            return Err(MemoryError::LoadOutOfBounds {
+                addr: 0,
+                len: map.len(),
+            });
+        }
+        self.map[..map.len()].copy_from_slice(map);
+        Ok(())
+    }
+
     pub fn get_memory_size(&self) -> u16 {
         self.memory_size
     }
 
+    pub fn load_address(&self) -> u16 {
+        self.load_address
+    }
+
+    pub fn add_watchpoint(&mut self, addr: u16, kind: WatchKind) {
+        self.watchpoints.insert(addr, kind);
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: u16) {
+        self.watchpoints.remove(&addr);
+    }
+
+    /// Returns the first watched access since the previous call, if any.
+    pub fn take_watch_hit(&mut self) -> Option<(u16, WatchKind)> {
+        self.watch_hit.take()
+    }
+
+    fn check_watchpoint(&self, addr: u16, access: WatchKind) {
+        if self.watch_hit.get().is_none()
+            && self
+                .watchpoints
+                .get(&addr)
+                .is_some_and(|kind| kind.matches(access))
+        {
+            self.watch_hit.set(Some((addr, access)));
+        }
+    }
+
     fn load_font_sprites(&mut self) {
-        let mut font_sprites = vec![];
-
-        font_sprites.extend_from_slice(&[
-            0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-            0x20, 0x60, 0x20, 0x20, 0x70, // 1
-            0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-            0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-            0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-            0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-            0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-            0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-            0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-            0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-            0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-            0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-            0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-            0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-            0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-            0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-        ]);
-
-        if self.mode != &ChipMode::Chip8 {
+        let mut font_sprites = self.font.to_vec();
+
+        if self.mode != ChipMode::Chip8 {
             font_sprites.extend_from_slice(&[
                 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
                 0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
@@ -161,7 +361,7 @@ impl<'a> Memory<'a> {
                 0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
                 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
                 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
-                0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFf, 0xC3, 0xC3, 0xC3, // A
+                0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
                 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
                 0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
                 0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
@@ -171,7 +371,41 @@ impl<'a> Memory<'a> {
         };
 
         font_sprites.into_iter().enumerate().for_each(|(i, val)| {
-            self.map[i] = val;
+            self.map[self.font_address as usize + i] = val;
         })
     }
 }
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryError::EmptyProgram => write!(f, "Program is empty"),
+            MemoryError::InvalidLoadAddress(addr) => {
+                write!(f, "Program can't be loaded at 0x{addr:04X}")
+            }
+            MemoryError::ProgramTooLarge { size, max_size } => write!(
+                f,
+                "Program is too large: {size} bytes, at most {max_size} bytes fit into the memory"
+            ),
+            MemoryError::LoadOutOfBounds { addr, len } => {
+                write!(f, "{len} bytes at 0x{addr:04X} don't fit into the memory")
+            }
+            MemoryError::WriteToReserved(addr) => write!(
+                f,
+                "Attempted to write to CHIP-8 interpreter address space: 0x{addr:04X}"
+            ),
+            MemoryError::OutOfBounds(addr) => {
+                write!(
+                    f,
+                    "Attempted to access the out-of-bound address: 0x{addr:04X}"
+                )
+            }
+            MemoryError::FontOutOfBounds(addr) => write!(
+                f,
+                "Font at 0x{addr:04X} doesn't fit into the interpreter address space"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
@@ -0,0 +1,237 @@
+use crate::instruction::Instruction;
+use crate::platform::ChipMode;
+
+/// A decoded instruction with its operands. `x` and `y` are register
+/// indexes, `kk` is a byte and `nnn` an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// 00CN - Scroll the display N lines down.
+    Scd(u8),
+    /// 00DN - Scroll the display N lines up.
+    Scu(u8),
+    /// 00E0 - Clear the display.
+    Cls,
+    /// 00EE - Return from a subroutine.
+    Ret,
+    /// 00FB - Scroll the display 4 pixels right.
+    Scr,
+    /// 00FC - Scroll the display 4 pixels left.
+    Scl,
+    /// 00FD - Exit the interpreter.
+    Exit,
+    /// 00FE - Switch to the low resolution.
+    Low,
+    /// 00FF - Switch to the high resolution.
+    High,
+    /// 0nnn - Machine code routine, executed as a jump to nnn.
+    Sys(u16),
+    /// 1nnn - Jump to nnn.
+    Jp(u16),
+    /// 2nnn - Call the subroutine at nnn.
+    Call(u16),
+    /// 3xkk - Skip the next instruction if Vx = kk.
+    SeByte { x: u8, kk: u8 },
+    /// 4xkk - Skip the next instruction if Vx != kk.
+    SneByte { x: u8, kk: u8 },
+    /// 5xy0 - Skip the next instruction if Vx = Vy.
+    SeReg { x: u8, y: u8 },
+    /// 5xy2 - Save Vx..Vy to memory starting at `I`.
+    Save { x: u8, y: u8 },
+    /// 5xy3 - Load Vx..Vy from memory starting at `I`.
+    Load { x: u8, y: u8 },
+    /// 6xkk - Set Vx = kk.
+    LdByte { x: u8, kk: u8 },
+    /// 7xkk - Set Vx = Vx + kk.
+    AddByte { x: u8, kk: u8 },
+    /// 8xy0 - Set Vx = Vy.
+    LdReg { x: u8, y: u8 },
+    /// 8xy1 - Set Vx = Vx OR Vy.
+    Or { x: u8, y: u8 },
+    /// 8xy2 - Set Vx = Vx AND Vy.
+    And { x: u8, y: u8 },
+    /// 8xy3 - Set Vx = Vx XOR Vy.
+    Xor { x: u8, y: u8 },
+    /// 8xy4 - Set Vx = Vx + Vy, VF = carry.
+    AddReg { x: u8, y: u8 },
+    /// 8xy5 - Set Vx = Vx - Vy, VF = NOT borrow.
+    Sub { x: u8, y: u8 },
+    /// 8xy6 - Set Vx = Vx SHR 1.
+    Shr { x: u8, y: u8 },
+    /// 8xy7 - Set Vx = Vy - Vx, VF = NOT borrow.
+    Subn { x: u8, y: u8 },
+    /// 8xyE - Set Vx = Vx SHL 1.
+    Shl { x: u8, y: u8 },
+    /// 9xy0 - Skip the next instruction if Vx != Vy.
+    SneReg { x: u8, y: u8 },
+    /// Annn - Set I = nnn.
+    LdI(u16),
+    /// Bnnn - Jump to nnn + V0.
+    JpV0(u16),
+    /// Cxkk - Set Vx = random byte AND kk.
+    Rnd { x: u8, kk: u8 },
+    /// Dxyn - Draw an n-byte sprite at (Vx, Vy).
+    Drw { x: u8, y: u8, n: u8 },
+    /// Ex9E - Skip the next instruction if the key Vx is pressed.
+    Skp(u8),
+    /// ExA1 - Skip the next instruction if the key Vx is not pressed.
+    Sknp(u8),
+    /// F000 nnnn - Set I to the following 16-bit address.
+    LdILong,
+    /// Fx01 - Select the drawing planes.
+    Plane(u8),
+    /// F002 - Load the audio pattern buffer from memory at `I`.
+    Audio,
+    /// Fx07 - Set Vx = delay timer.
+    LdVxDt(u8),
+    /// Fx0A - Wait for a key press and store it in Vx.
+    LdVxK(u8),
+    /// Fx15 - Set delay timer = Vx.
+    LdDtVx(u8),
+    /// Fx18 - Set sound timer = Vx.
+    LdStVx(u8),
+    /// Fx1E - Set I = I + Vx.
+    AddIVx(u8),
+    /// Fx29 - Set I to the small font sprite of the digit Vx.
+    LdFVx(u8),
+    /// Fx30 - Set I to the large font sprite of the digit Vx.
+    LdHfVx(u8),
+    /// Fx33 - Store the BCD representation of Vx at I, I+1 and I+2.
+    LdBVx(u8),
+    /// Fx3A - Set the audio pattern playback rate from Vx.
+    Pitch(u8),
+    /// Fx55 - Store V0..Vx in memory starting at `I`.
+    LdIVx(u8),
+    /// Fx65 - Read V0..Vx from memory starting at `I`.
+    LdVxI(u8),
+    /// Fx75 - Store V0..Vx in the RPL user flags.
+    LdRVx(u8),
+    /// Fx85 - Read V0..Vx from the RPL user flags.
+    LdVxR(u8),
+    /// 0010 - Turn the MEGA-CHIP mode off.
+    MegaOff,
+    /// 0011 - Turn the MEGA-CHIP mode on.
+    MegaOn,
+    /// 01nn nnnn - Set I to the following 24-bit address, nn is its high
+    /// byte.
+    LdIHi(u8),
+    /// 02nn - Load nn colors of the palette from memory at `I`.
+    LdPal(u8),
+    /// 03nn - Set the sprite width to nn.
+    SprW(u8),
+    /// 04nn - Set the sprite height to nn.
+    SprH(u8),
+    /// 05nn - Set the screen alpha to nn.
+    Alpha(u8),
+    /// 060n - Play the digitized sound at `I`, n is the loop flag.
+    DigiSnd(u8),
+    /// 0700 - Stop the digitized sound.
+    StopSnd,
+    /// 080n - Set the sprite blend mode.
+    BMode(u8),
+    /// 09nn - Set the collision color index to nn.
+    CCol(u8),
+}
+
+impl Op {
+    /// MEGA-CHIP instructions that are decoded but can't be executed yet:
+    /// everything except `00BN` scrolling, which is `Scu`, and switching
+    /// the 256x192 screen with `0010`/`0011`.
+    pub fn is_unimplemented(&self) -> bool {
+        matches!(
+            self,
+            Op::LdIHi(_)
+                | Op::LdPal(_)
+                | Op::SprW(_)
+                | Op::SprH(_)
+                | Op::Alpha(_)
+                | Op::DigiSnd(_)
+                | Op::StopSnd
+                | Op::BMode(_)
+                | Op::CCol(_)
+        )
+    }
+}
+
+/// Decodes `opcode` as `mode` understands it. `None` means the opcode is
+/// illegal for the mode.
+pub fn decode(opcode: u16, mode: &ChipMode) -> Option<Op> {
+    let instruction = Instruction::new(opcode);
+    let x = instruction.x();
+    let y = instruction.y();
+    let kk = instruction.kk();
+    let nnn = instruction.nnn();
+    let op = match (mode, instruction.nibbles()) {
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xC, n)) if n > 0 => {
+            Op::Scd(n)
+        }
+        (ChipMode::XOChip, (0, 0, 0xD, n)) => Op::Scu(n),
+        (ChipMode::MegaChip, (0, 0, 0xB, n)) => Op::Scu(n),
+        (ChipMode::MegaChip, (0, 0, 1, 0)) => Op::MegaOff,
+        (ChipMode::MegaChip, (0, 0, 1, 1)) => Op::MegaOn,
+        (ChipMode::MegaChip, (0, 1, ..)) => Op::LdIHi(kk),
+        (ChipMode::MegaChip, (0, 2, ..)) => Op::LdPal(kk),
+        (ChipMode::MegaChip, (0, 3, ..)) => Op::SprW(kk),
+        (ChipMode::MegaChip, (0, 4, ..)) => Op::SprH(kk),
+        (ChipMode::MegaChip, (0, 5, ..)) => Op::Alpha(kk),
+        (ChipMode::MegaChip, (0, 6, 0, n)) => Op::DigiSnd(n),
+        (ChipMode::MegaChip, (0, 7, 0, 0)) => Op::StopSnd,
+        (ChipMode::MegaChip, (0, 8, 0, n)) => Op::BMode(n),
+        (ChipMode::MegaChip, (0, 9, ..)) => Op::CCol(kk),
+        (_, (0, 0, 0xE, 0)) => Op::Cls,
+        (_, (0, 0, 0xE, 0xE)) => Op::Ret,
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xF, 0xB)) => Op::Scr,
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xF, 0xC)) => Op::Scl,
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xF, 0xD)) => Op::Exit,
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xF, 0xE)) => Op::Low,
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0, 0, 0xF, 0xF)) => Op::High,
+        (ChipMode::Chip8, (0, ..)) => Op::Sys(nnn),
+        (_, (1, ..)) => Op::Jp(nnn),
+        (_, (2, ..)) => Op::Call(nnn),
+        (_, (3, ..)) => Op::SeByte { x, kk },
+        (_, (4, ..)) => Op::SneByte { x, kk },
+        (ChipMode::XOChip, (5, .., 2)) => Op::Save { x, y },
+        (ChipMode::XOChip, (5, .., 3)) => Op::Load { x, y },
+        (_, (5, .., 0)) => Op::SeReg { x, y },
+        (_, (6, ..)) => Op::LdByte { x, kk },
+        (_, (7, ..)) => Op::AddByte { x, kk },
+        (_, (8, .., 0)) => Op::LdReg { x, y },
+        (_, (8, .., 1)) => Op::Or { x, y },
+        (_, (8, .., 2)) => Op::And { x, y },
+        (_, (8, .., 3)) => Op::Xor { x, y },
+        (_, (8, .., 4)) => Op::AddReg { x, y },
+        (_, (8, .., 5)) => Op::Sub { x, y },
+        (_, (8, .., 6)) => Op::Shr { x, y },
+        (_, (8, .., 7)) => Op::Subn { x, y },
+        (_, (8, .., 0xE)) => Op::Shl { x, y },
+        (_, (9, .., 0)) => Op::SneReg { x, y },
+        (_, (0xA, ..)) => Op::LdI(nnn),
+        (_, (0xB, ..)) => Op::JpV0(nnn),
+        (_, (0xC, ..)) => Op::Rnd { x, kk },
+        (_, (0xD, .., n)) => Op::Drw { x, y, n },
+        (_, (0xE, _, 0x9, 0xE)) => Op::Skp(x),
+        (_, (0xE, _, 0xA, 1)) => Op::Sknp(x),
+        (ChipMode::XOChip, (0xF, 0, 0, 0)) => Op::LdILong,
+        (ChipMode::XOChip, (0xF, _, 0, 1)) if x <= 3 => Op::Plane(x),
+        (ChipMode::XOChip, (0xF, 0, 0, 2)) => Op::Audio,
+        (_, (0xF, _, 0, 7)) => Op::LdVxDt(x),
+        (_, (0xF, _, 0, 0xA)) => Op::LdVxK(x),
+        (_, (0xF, _, 1, 5)) => Op::LdDtVx(x),
+        (_, (0xF, _, 1, 8)) => Op::LdStVx(x),
+        (_, (0xF, _, 1, 0xE)) => Op::AddIVx(x),
+        (_, (0xF, _, 2, 9)) => Op::LdFVx(x),
+        (ChipMode::SuperChip | ChipMode::XOChip | ChipMode::MegaChip, (0xF, _, 3, 0)) => {
+            Op::LdHfVx(x)
+        }
+        (_, (0xF, _, 3, 3)) => Op::LdBVx(x),
+        (ChipMode::XOChip, (0xF, _, 3, 0xA)) => Op::Pitch(x),
+        (_, (0xF, _, 5, 5)) => Op::LdIVx(x),
+        (_, (0xF, _, 6, 5)) => Op::LdVxI(x),
+        // SUPER-CHIP has 8 user flags, XO-CHIP has 16.
+        (ChipMode::SuperChip | ChipMode::MegaChip, (0xF, _, 7, 5)) if x <= 7 => Op::LdRVx(x),
+        (ChipMode::SuperChip | ChipMode::MegaChip, (0xF, _, 8, 5)) if x <= 7 => Op::LdVxR(x),
+        (ChipMode::XOChip, (0xF, _, 7, 5)) => Op::LdRVx(x),
+        (ChipMode::XOChip, (0xF, _, 8, 5)) => Op::LdVxR(x),
+        _ => return None,
+    };
+    Some(op)
+}
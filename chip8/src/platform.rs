@@ -1,13 +1,24 @@
+use std::collections::HashSet;
 use std::fmt::Display;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ChipMode {
     Chip8,
     SuperChip,
     XOChip,
+    /// MEGA-CHIP, a SUPER-CHIP extension with a 256x192 screen and a
+    /// 256-color palette.
+    ///
+    /// Only its SUPER-CHIP part, `00BN` scrolling and switching to the
+    /// 256x192 screen with `0010`/`0011` run so far, sprites are still
+    /// drawn in the two planes. `01NN`, `02NN`, `03NN`, `04NN`, `05NN`,
+    /// `060N`, `0700`, `080N` and `09NN` are decoded, but executing them
+    /// fails with `Chip8Error::UnimplementedInstruction` until the color
+    /// palette and the digitized sound are in place.
+    MegaChip,
 }
 
-#[derive(Hash, Eq, PartialEq)]
+#[derive(Clone, Copy, Hash, Eq, PartialEq)]
 pub enum Quirks {
     /// For `FX55` and `FX65` instructions.
     ///
@@ -30,8 +41,18 @@ pub enum Quirks {
     /// Starting with CHIP-48 and SUPER-CHIP, it was (probably unintentionally)
     /// changed to work as `BXNN`: It will jump to the address `XNN`,
     /// plus the value in the register `VX`.
+    ///
+    /// SUPER-CHIP jumps this way even without the quirk, it only has to be
+    /// enabled for the other platforms.
     JumpWithX,
 
+    /// For `BNNN` instruction.
+    ///
+    /// Makes SUPER-CHIP jump to the address NNN plus the value in the
+    /// register V0, as the other platforms do. Takes precedence over
+    /// `JumpWithX`.
+    JumpWithV0,
+
     /// For `8XY6` and `8XYE` instructions.
     ///
     /// In the CHIP-8 interpreter, this instruction did the following:
@@ -51,8 +72,95 @@ pub enum Quirks {
     /// the end.
     BinaryOpResetVF,
 
-    /// Wraps pixels instead of clipping them.
+    /// Wraps pixels instead of clipping them on both axes.
     WrapsInsteadClipping,
+
+    /// For `DXYN` instruction.
+    ///
+    /// Wraps pixels past the right edge to the left side of the screen,
+    /// while the bottom edge keeps clipping unless `WrapsVertically` or
+    /// `WrapsInsteadClipping` is enabled too.
+    WrapsHorizontally,
+
+    /// For `DXYN` instruction.
+    ///
+    /// Wraps pixels past the bottom edge to the top of the screen, while
+    /// the right edge keeps clipping unless `WrapsHorizontally` or
+    /// `WrapsInsteadClipping` is enabled too.
+    WrapsVertically,
+
+    /// For `FX1E` instruction.
+    ///
+    /// The CHIP-8 interpreter for the Amiga set `VF` to 1 when `I`
+    /// overflowed past the addressable memory, and to 0 otherwise.
+    /// At least one known game, Spacefight 2091!, relies on this.
+    AddIOverflowSetsVF,
+
+    /// For `FX0A` instruction.
+    ///
+    /// On the COSMAC VIP the instruction waited for a key to be pressed
+    /// and then released, and only then stored it in `VX`. Otherwise a
+    /// held key is registered on every execution.
+    WaitForKeyRelease,
+
+    /// For `00FE` and `00FF` instructions.
+    ///
+    /// SUPER-CHIP hardware keeps the screen content when switching
+    /// between low and high resolution. Some interpreters, XO-CHIP
+    /// included, clear the screen instead.
+    ClearOnModeSwitch,
+
+    /// For `DXYN` instruction.
+    ///
+    /// The COSMAC VIP waited for the vertical blank interrupt before
+    /// drawing, so at most one sprite was drawn per frame. With this quirk
+    /// `DXYN` ends the current frame, the remaining instructions of the
+    /// `ticks_per_frame` budget are not executed.
+    DisplayWait,
+
+    /// For `00CN`, `00FB` and `00FC` instructions.
+    ///
+    /// SUPER-CHIP draws the low resolution screen on the high resolution
+    /// one, so the scroll instructions move the low resolution screen by
+    /// half the distance. XO-CHIP ignores it.
+    LoresHalfScroll,
+
+    /// For `DXYN` instruction in low resolution.
+    ///
+    /// SUPER-CHIP has only the high resolution screen, in low resolution
+    /// every pixel of a sprite is drawn as a 2x2 block on it. The screen
+    /// keeps its content when the resolution changes and pixels collide
+    /// per block. Without this quirk the low resolution screen is a
+    /// separate 64x32 buffer. XO-CHIP ignores it.
+    LoresWideSprites,
+}
+
+impl Quirks {
+    /// The quirks of the canonical interpreter of each platform:
+    /// * CHIP-8 behaves as the COSMAC VIP: `IRegisterIncrementedWithX`,
+    ///   `BinaryOpResetVF`, `WaitForKeyRelease` and `DisplayWait`.
+    /// * SUPER-CHIP behaves as SUPER-CHIP 1.1 on the HP 48: `ShiftIgnoreVY`.
+    ///   Its `BXNN` jump is the default for the platform.
+    /// * XO-CHIP behaves as Octo: `IRegisterIncrementedWithX`,
+    ///   `WrapsInsteadClipping` and `ClearOnModeSwitch`.
+    pub fn preset_for(mode: &ChipMode) -> HashSet<Quirks> {
+        match mode {
+            ChipMode::Chip8 => HashSet::from([
+                Quirks::IRegisterIncrementedWithX,
+                Quirks::BinaryOpResetVF,
+                Quirks::WaitForKeyRelease,
+                Quirks::DisplayWait,
+            ]),
+            ChipMode::SuperChip | ChipMode::MegaChip => HashSet::from([Quirks::ShiftIgnoreVY]),
+            // `JumpWithX` is left out on purpose: Octo, the reference XO-CHIP
+            // implementation, keeps the original `BNNN` behaviour and adds V0.
+            ChipMode::XOChip => HashSet::from([
+                Quirks::IRegisterIncrementedWithX,
+                Quirks::WrapsInsteadClipping,
+                Quirks::ClearOnModeSwitch,
+            ]),
+        }
+    }
 }
 
 impl Display for ChipMode {
@@ -61,6 +169,7 @@ impl Display for ChipMode {
             ChipMode::Chip8 => write!(f, "CHIP-8"),
             ChipMode::SuperChip => write!(f, "SUPER-CHIP"),
             ChipMode::XOChip => write!(f, "XO-CHIP"),
+            ChipMode::MegaChip => write!(f, "MEGA-CHIP"),
         }
     }
 }
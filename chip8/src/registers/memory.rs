@@ -22,4 +22,9 @@ impl MemoryRegister {
     pub fn add(&self, value: u16) -> u16 {
         (self.value.wrapping_add(value)) & self.memory_limit
     }
+
+    /// Whether adding `value` goes past the memory limit.
+    pub fn overflows(&self, value: u16) -> bool {
+        self.value as u32 + value as u32 > self.memory_limit as u32
+    }
 }
@@ -1,19 +1,14 @@
+#[derive(Default)]
 pub struct TimerRegister {
     value: u8,
 }
 
-impl Default for TimerRegister {
-    fn default() -> Self {
-        TimerRegister { value: 0 }
-    }
-}
-
 impl TimerRegister {
     pub fn set(&mut self, value: u8) {
         self.value = value;
     }
 
-    pub fn get(&mut self) -> u8 {
+    pub fn get(&self) -> u8 {
         self.value
     }
 
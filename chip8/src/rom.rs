@@ -1,15 +1,206 @@
+use crate::instruction::Instruction;
+use crate::platform::ChipMode;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
 pub struct Rom {
     content: Vec<u8>,
 }
 
+#[derive(Debug)]
+pub enum RomError {
+    /// The ROM file doesn't exist.
+    NotFound,
+    /// The ROM file couldn't be read.
+    Io(std::io::Error),
+    /// The program doesn't fit into the addressable memory.
+    TooLarge(usize),
+}
+
 impl Rom {
-    pub fn new(file_path: &str) -> Rom {
-        // TODO: add exception instead of panic
-        let content = std::fs::read(file_path).unwrap();
-        Rom { content }
+    /// The biggest program that fits into the memory, i.e. the XO-CHIP
+    /// 64KB address space minus the interpreter area.
+    pub const MAX_SIZE: usize = 0x10000 - 0x200;
+
+    /// The biggest program that fits into the 4KB of CHIP-8 and SUPER-CHIP.
+    const MAX_4KB_SIZE: usize = 0x1000 - 0x200;
+
+    const PROGRAM_ADDR_START: u16 = 0x200;
+
+    pub fn new(file_path: impl AsRef<Path>) -> Result<Rom, RomError> {
+        let file = File::open(file_path).map_err(|err| match err.kind() {
+            ErrorKind::NotFound => RomError::NotFound,
+            _ => RomError::Io(err),
+        })?;
+        Rom::from_reader(file)
+    }
+
+    /// Reads the program from `reader` to the end, e.g. from stdin or an
+    /// archive entry.
+    pub fn from_reader(mut reader: impl Read) -> Result<Rom, RomError> {
+        let mut content = vec![];
+        reader.read_to_end(&mut content).map_err(RomError::Io)?;
+        if content.len() > Self::MAX_SIZE {
+            return Err(RomError::TooLarge(content.len()));
+        }
+        Ok(Rom::from_bytes(content))
+    }
+
+    /// Creates a ROM from a program that is already in memory, e.g. one
+    /// embedded with `include_bytes!`.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Rom {
+        Rom {
+            content: bytes.into(),
+        }
     }
 
     pub fn content(&self) -> &[u8] {
         &self.content
     }
+
+    /// Suggests the platform the program was written for by looking for
+    /// the opcodes only SUPER-CHIP, XO-CHIP or MEGA-CHIP have:
+    /// * `00FE`, `00FF`, `00FB`, `00FC`, `00CN`, `Fx30`, `Fx75`, `Fx85`
+    ///   imply SUPER-CHIP.
+    /// * `F000`, `Fx01`, `F002`, `Fx3A`, `00DN`, `5xy2`, `5xy3` imply
+    ///   XO-CHIP.
+    /// * `0011` (turning the MEGA-CHIP mode on) implies MEGA-CHIP.
+    ///
+    /// Only the instructions reachable from the start of the program are
+    /// checked, because sprite data often looks like opcodes. `None` is
+    /// returned when the program jumps to computed addresses with `Bnnn`
+    /// and no extended opcode was found, or when it doesn't fit into the
+    /// memory of the platform its opcodes imply.
+    pub fn detect_platform(&self) -> Option<ChipMode> {
+        let mut super_chip_opcodes = 0;
+        let mut xo_chip_opcodes = 0;
+        let mut mega_chip_opcodes = 0;
+        let mut has_computed_jumps = false;
+        self.reachable_opcodes().into_iter().for_each(|opcode| {
+            match Instruction::new(opcode).nibbles() {
+                (0, 0, 0xF, 0xB..=0xF) | (0, 0, 0xC, 1..) | (0xF, _, 3, 0) | (0xF, _, 7 | 8, 5) => {
+                    super_chip_opcodes += 1
+                }
+                (0xF, 0, 0, 0 | 2) | (0xF, 0..=3, 0, 1) | (0xF, _, 3, 0xA) | (0, 0, 0xD, _) => {
+                    xo_chip_opcodes += 1
+                }
+                (5, _, _, 2 | 3) => xo_chip_opcodes += 1,
+                (0, 0, 1, 1) => mega_chip_opcodes += 1,
+                (0xB, ..) => has_computed_jumps = true,
+                _ => {}
+            }
+        });
+
+        let fits_into_4kb = self.content.len() <= Self::MAX_4KB_SIZE;
+        if mega_chip_opcodes > 0 {
+            return Some(ChipMode::MegaChip);
+        }
+        match (xo_chip_opcodes, super_chip_opcodes) {
+            (1.., _) => Some(ChipMode::XOChip),
+            _ if !fits_into_4kb => None,
+            (0, 1..) => Some(ChipMode::SuperChip),
+            _ if has_computed_jumps => None,
+            _ => Some(ChipMode::Chip8),
+        }
+    }
+
+    /// Follows jumps, calls and skips from the start of the program and
+    /// returns every opcode that can be executed.
+    fn reachable_opcodes(&self) -> Vec<u16> {
+        let mut opcodes = vec![];
+        let mut visited = HashSet::new();
+        let mut pending = vec![Self::PROGRAM_ADDR_START];
+        while let Some(addr) = pending.pop() {
+            let Some(offset) = addr.checked_sub(Self::PROGRAM_ADDR_START) else {
+                continue;
+            };
+            let offset = offset as usize;
+            if offset + 1 >= self.content.len() || !visited.insert(addr) {
+                continue;
+            }
+
+            let instruction = Instruction::new(u16::from_be_bytes([
+                self.content[offset],
+                self.content[offset + 1],
+            ]));
+            let next = addr.wrapping_add(2);
+            match instruction.nibbles() {
+                (0, 0, 0xE, 0xE) | (0, 0, 0xF, 0xD) | (0xB, ..) => {}
+                (1, ..) => pending.push(instruction.nnn()),
+                (2, ..) => pending.extend([instruction.nnn(), next]),
+                (3 | 4, ..) | (5 | 9, _, _, 0) | (0xE, _, 9, 0xE) | (0xE, _, 0xA, 1) => {
+                    pending.extend([next, next.wrapping_add(2)])
+                }
+                // The 2-byte address of `F000` must not be read as an opcode.
+                (0xF, 0, 0, 0) => pending.push(next.wrapping_add(2)),
+                _ => pending.push(next),
+            }
+            opcodes.push(instruction.value());
+        }
+        opcodes
+    }
+}
+
+impl Display for RomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RomError::NotFound => write!(f, "ROM file not found"),
+            RomError::Io(err) => write!(f, "Unable to read ROM file: {err}"),
+            RomError::TooLarge(size) => write!(
+                f,
+                "ROM is too large: {size} bytes, at most {} bytes are allowed",
+                Rom::MAX_SIZE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RomError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{Memory, MemoryError};
+
+    #[test]
+    fn oversized_rom_is_rejected() {
+        let bytes = vec![0; Rom::MAX_SIZE + 1];
+
+        assert!(matches!(
+            Rom::from_reader(bytes.as_slice()),
+            Err(RomError::TooLarge(size)) if size == Rom::MAX_SIZE + 1
+        ));
+        assert!(Rom::from_reader(&bytes[1..]).is_ok());
+    }
+
+    #[test]
+    fn rom_larger_than_4kb_memory_is_rejected() {
+        let rom = Rom::from_bytes(vec![0; Rom::MAX_4KB_SIZE + 1]);
+
+        assert!(matches!(
+            Memory::new(rom.content(), &ChipMode::Chip8),
+            Err(MemoryError::ProgramTooLarge { size, max_size })
+                if size == Rom::MAX_4KB_SIZE + 1 && max_size == Rom::MAX_4KB_SIZE
+        ));
+    }
+
+    #[test]
+    fn empty_rom_is_rejected() {
+        let rom = Rom::from_reader([].as_slice()).unwrap();
+
+        assert!(matches!(
+            Memory::new(rom.content(), &ChipMode::Chip8),
+            Err(MemoryError::EmptyProgram)
+        ));
+    }
 }
@@ -1,44 +1,124 @@
+use std::fmt::Display;
+
 /// The stack is an array of 16 16-bit values, used to store the address
 /// that the interpreter should return to when finished with a subroutine.
-/// Chip-8 allows for up to 16 levels of nested subroutines.
+/// Chip-8 allows for up to 16 levels of nested subroutines, some
+/// interpreters allow more.
 pub struct Stack {
-    /// It is used to point to the topmost level of the stack.
-    stack_pointer: u8,
-    stack: [u16; 16],
+    /// The topmost level of the stack is the last.
+    stack: Vec<u16>,
+    max_depth: u8,
     memory_limit: u16,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum StackError {
+    /// More nested subroutines were called than the stack holds.
+    Overflow,
+    /// Returned from a subroutine while none was called.
+    Underflow,
+}
+
 impl Stack {
+    pub const DEFAULT_DEPTH: u8 = 16;
+
     pub fn new(memory_limit: u16) -> Self {
+        Self::with_depth(memory_limit, Self::DEFAULT_DEPTH)
+    }
+
+    /// Same as `new`, but allows `max_depth` nested subroutines instead
+    /// of 16.
+    pub fn with_depth(memory_limit: u16, max_depth: u8) -> Self {
         Self {
+            stack: Vec::with_capacity(max_depth as usize),
+            max_depth,
             memory_limit,
-            ..Default::default()
         }
     }
 
-    pub fn push(&mut self, val: u16) {
-        if self.stack_pointer > 16 {
-            panic!("Stack is full.");
+    pub fn push(&mut self, val: u16) -> Result<(), StackError> {
+        if self.stack.len() >= self.max_depth as usize {
+            return Err(StackError::Overflow);
         }
-        self.stack[self.stack_pointer as usize] = val;
-        self.stack_pointer += 1;
+        self.stack.push(val);
+        Ok(())
     }
 
-    pub fn pull(&mut self) -> u16 {
-        if self.stack_pointer == 0 {
-            panic!("Can't pull because stack is empty.");
-        }
-        self.stack_pointer -= 1;
-        self.stack[self.stack_pointer as usize] & self.memory_limit
+    pub fn pull(&mut self) -> Result<u16, StackError> {
+        self.stack
+            .pop()
+            .map(|val| val & self.memory_limit)
+            .ok_or(StackError::Underflow)
+    }
+
+    /// Number of nested subroutines that are currently called.
+    pub fn depth(&self) -> u8 {
+        self.stack.len() as u8
+    }
+
+    /// Addresses that are currently on the stack, the topmost is the last.
+    pub fn frames(&self) -> &[u16] {
+        &self.stack
+    }
+
+    /// Replaces the stack content with `frames`, the ones that don't fit
+    /// are dropped.
+    pub fn load_frames(&mut self, frames: &[u16]) {
+        let len = frames.len().min(self.max_depth as usize);
+        self.stack.clear();
+        self.stack.extend_from_slice(&frames[..len]);
     }
 }
 
-impl Default for Stack {
-    fn default() -> Self {
-        Stack {
-            stack_pointer: 0,
-            stack: [0; 16],
-            memory_limit: 0,
+impl Display for StackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StackError::Overflow => write!(f, "Stack is full."),
+            StackError::Underflow => write!(f, "Can't pull because stack is empty."),
         }
     }
 }
+
+impl std::error::Error for StackError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_past_the_default_depth_overflows() {
+        let mut stack = Stack::new(0x0FFF);
+        (0..16).for_each(|frame| stack.push(0x200 + frame * 2).unwrap());
+
+        assert_eq!(stack.push(0x220), Err(StackError::Overflow));
+        assert_eq!(stack.depth(), 16);
+    }
+
+    #[test]
+    fn pull_from_empty_stack_underflows() {
+        let mut stack = Stack::new(0x0FFF);
+        stack.push(0x202).unwrap();
+
+        assert_eq!(stack.pull(), Ok(0x202));
+        assert_eq!(stack.pull(), Err(StackError::Underflow));
+    }
+
+    #[test]
+    fn deeper_stack_accepts_more_frames() {
+        let mut stack = Stack::with_depth(0x0FFF, 32);
+        (0..20).for_each(|frame| stack.push(0x200 + frame * 2).unwrap());
+
+        assert_eq!(stack.depth(), 20);
+        assert_eq!(stack.pull(), Ok(0x200 + 19 * 2));
+    }
+
+    #[test]
+    fn default_depth_rejects_20_frames() {
+        let mut stack = Stack::new(0x0FFF);
+        let pushed = (0..20)
+            .take_while(|frame| stack.push(0x200 + frame * 2).is_ok())
+            .count();
+
+        assert_eq!(pushed, Stack::DEFAULT_DEPTH as usize);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::display::Plane;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the whole machine. It is taken with `Chip8::save_state`
+/// and restored with `Chip8::load_state`.
+///
+/// With the `serde` feature enabled it can be serialized, e.g. to keep
+/// save slots on disk.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MachineState {
+    pub(crate) memory: Vec<u8>,
+    pub(crate) rpl_flags: Vec<u8>,
+    pub(crate) registers: [u8; 16],
+    pub(crate) i_register: u16,
+    pub(crate) program_counter: u16,
+    pub(crate) halted: bool,
+    pub(crate) stack: Vec<u16>,
+    pub(crate) delay_timer: u8,
+    pub(crate) sound_timer: u8,
+    pub(crate) first_plane: Vec<bool>,
+    pub(crate) second_plane: Vec<bool>,
+    pub(crate) current_plane: Plane,
+    pub(crate) is_hires: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) is_hires_64x64: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) is_mega: bool,
+    pub(crate) audio_buffer: [u8; 16],
+    pub(crate) pitch: u16,
+    pub(crate) awaited_key: Option<u8>,
+}
+
+/// Version of the schema written by `Chip8::state_json`. It is bumped
+/// whenever a field is renamed, removed or changes its meaning.
+#[cfg(feature = "serde")]
+pub const STATE_JSON_VERSION: u32 = 1;
+
+/// The machine as external debuggers see it, see `Chip8::state_json`.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct StateJson<'a> {
+    version: u32,
+    pc: u16,
+    i: u16,
+    /// V0 to VF.
+    registers: &'a [u8; 16],
+    /// Return addresses, the innermost call is the last one.
+    stack: &'a [u16],
+    delay_timer: u8,
+    sound_timer: u8,
+    halted: bool,
+    hires: bool,
+    hires_64x64: bool,
+    mega: bool,
+    plane: Plane,
+    awaited_key: Option<u8>,
+    pitch: u16,
+    rpl_flags: &'a [u8],
+    /// The whole memory, base64 with padding.
+    memory: String,
+}
+
+#[cfg(feature = "serde")]
+impl MachineState {
+    /// Serializes the snapshot in the `Chip8::state_json` schema.
+    pub fn to_json(&self) -> String {
+        use base64::Engine;
+        use base64::engine::general_purpose::STANDARD;
+
+        serde_json::to_string(&StateJson {
+            version: STATE_JSON_VERSION,
+            pc: self.program_counter,
+            i: self.i_register,
+            registers: &self.registers,
+            stack: &self.stack,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            halted: self.halted,
+            hires: self.is_hires,
+            hires_64x64: self.is_hires_64x64,
+            mega: self.is_mega,
+            plane: self.current_plane,
+            awaited_key: self.awaited_key,
+            pitch: self.pitch,
+            rpl_flags: &self.rpl_flags,
+            memory: STANDARD.encode(&self.memory),
+        })
+        .expect("The state is always serializable")
+    }
+}
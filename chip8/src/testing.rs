@@ -0,0 +1,152 @@
+//! Helpers for compatibility tests that run a ROM headless and compare the
+//! screen with the expected one, e.g. for the Timendus test suite.
+
+use crate::chip::Chip8;
+use crate::platform::{ChipMode, Quirks};
+use crate::rom::Rom;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Instructions executed per frame by `run_and_capture`, enough for the
+/// test ROMs to draw their results in a few frames.
+pub const TICKS_PER_FRAME: u32 = 1000;
+
+/// Runs `rom_bytes` for `frames` frames with a fixed seed and returns the
+/// first plane, `width() * height()` pixels row by row.
+///
+/// Panics if the program can't be loaded or fails while running.
+pub fn run_and_capture(
+    rom_bytes: &[u8],
+    mode: &ChipMode,
+    quirks: &HashSet<Quirks>,
+    frames: u32,
+) -> Vec<bool> {
+    let mut chip8 = Chip8::with_seed(
+        Rom::from_bytes(rom_bytes),
+        mode,
+        quirks,
+        TICKS_PER_FRAME,
+        None,
+        0,
+    )
+    .unwrap_or_else(|err| panic!("Unable to load the program: {err}"));
+    (0..frames).for_each(|_| {
+        chip8
+            .step_frame()
+            .unwrap_or_else(|err| panic!("Program failed: {err}"));
+    });
+    chip8.display().buffer().to_vec()
+}
+
+/// Panics unless the first plane of `machine` equals `expected`. The
+/// message shows the region with the mismatched pixels as ASCII art: the
+/// expected pixels, the actual ones and an `X` for every difference.
+pub fn assert_display_matches(machine: &Chip8, expected: &[bool]) {
+    let display = machine.display();
+    let actual = display.buffer();
+    let width = display.width();
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "The screen has {} pixels, {} are expected",
+        actual.len(),
+        expected.len()
+    );
+
+    let mismatches = (0..actual.len())
+        .filter(|&pixel| actual[pixel] != expected[pixel])
+        .map(|pixel| (pixel % width, pixel / width))
+        .collect::<Vec<_>>();
+    if mismatches.is_empty() {
+        return;
+    }
+
+    let left = mismatches.iter().map(|&(x, _)| x).min().unwrap();
+    let right = mismatches.iter().map(|&(x, _)| x).max().unwrap();
+    let top = mismatches.iter().map(|&(_, y)| y).min().unwrap();
+    let bottom = mismatches.iter().map(|&(_, y)| y).max().unwrap();
+    let row = |pixels: &[bool], y: usize| {
+        (left..=right)
+            .map(|x| if pixels[x + y * width] { '#' } else { '.' })
+            .collect::<String>()
+    };
+
+    let mut message = format!(
+        "{} pixels differ in ({left}, {top})..=({right}, {bottom})\nexpected | actual | diff\n",
+        mismatches.len()
+    );
+    (top..=bottom).for_each(|y| {
+        let diff = (left..=right)
+            .map(|x| {
+                let pixel = x + y * width;
+                if actual[pixel] != expected[pixel] {
+                    'X'
+                } else {
+                    ' '
+                }
+            })
+            .collect::<String>();
+        writeln!(
+            message,
+            "{} | {} | {}",
+            row(expected, y),
+            row(actual, y),
+            diff
+        )
+        .unwrap();
+    });
+    panic!("{message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws the font digit 0 at (0, 0) and loops forever.
+    const DIGIT_0_ROM: [u8; 6] = [0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04];
+
+    /// The lores screen with the font digit 0 drawn at (0, 0).
+    fn digit_0_screen() -> Vec<bool> {
+        let mut screen = vec![false; 64 * 32];
+        [0xF0u8, 0x90, 0x90, 0x90, 0xF0]
+            .iter()
+            .enumerate()
+            .for_each(|(y, row)| {
+                (0..8).for_each(|x| screen[x + y * 64] = row & (0x80 >> x) != 0);
+            });
+        screen
+    }
+
+    fn digit_0_machine(mode: &ChipMode, quirks: &HashSet<Quirks>) -> Chip8 {
+        let mut chip8 =
+            Chip8::with_seed(Rom::from_bytes(DIGIT_0_ROM), mode, quirks, 1, None, 0).unwrap();
+        (0..2).for_each(|_| {
+            chip8.step().unwrap();
+        });
+        chip8
+    }
+
+    #[test]
+    fn captures_the_drawn_glyph() {
+        let quirks = HashSet::new();
+        let screen = run_and_capture(&DIGIT_0_ROM, &ChipMode::Chip8, &quirks, 2);
+        assert_eq!(screen, digit_0_screen());
+    }
+
+    #[test]
+    fn matching_display_passes() {
+        let quirks = HashSet::new();
+        let chip8 = digit_0_machine(&ChipMode::Chip8, &quirks);
+        assert_display_matches(&chip8, &digit_0_screen());
+    }
+
+    #[test]
+    #[should_panic(expected = "1 pixels differ in (7, 4)..=(7, 4)")]
+    fn mismatched_display_shows_the_difference() {
+        let quirks = HashSet::new();
+        let chip8 = digit_0_machine(&ChipMode::Chip8, &quirks);
+        let mut expected = digit_0_screen();
+        expected[7 + 4 * 64] = true;
+        assert_display_matches(&chip8, &expected);
+    }
+}
@@ -0,0 +1,112 @@
+//! Bindings for running the emulator in a browser with `wasm-bindgen`.
+//!
+//! The page drives the emulator itself: it calls `step_frame` 60 times a
+//! second, draws `framebuffer` and forwards the keypad input. Nothing here
+//! sleeps, spawns threads or touches the filesystem.
+
+use crate::chip::Chip8;
+use crate::display::Color;
+use crate::platform::{ChipMode, Quirks};
+use crate::rom::Rom;
+use wasm_bindgen::prelude::*;
+
+/// RGBA of the disabled, first plane, second plane and both planes
+/// pixels, the same as the default CLI palette.
+const PALETTE: [[u8; 4]; 4] = [
+    [0x00, 0x00, 0x00, 0xFF],
+    [0xFF, 0x00, 0x00, 0xFF],
+    [0x00, 0xFF, 0x00, 0xFF],
+    [0x00, 0x00, 0xFF, 0xFF],
+];
+
+#[wasm_bindgen]
+pub struct WasmChip8 {
+    chip8: Chip8,
+    /// Reused by `framebuffer`, it follows the resolution of the screen.
+    bitplane: Vec<Color>,
+}
+
+#[wasm_bindgen]
+impl WasmChip8 {
+    /// Loads the program from `bytes`. `platform` is 0 for CHIP-8, 1 for
+    /// SUPER-CHIP, 2 for XO-CHIP and 3 for MEGA-CHIP, the quirks are the
+    /// platform preset.
+    pub fn new_from_bytes(
+        bytes: &[u8],
+        platform: u8,
+        ticks_per_frame: u32,
+    ) -> Result<WasmChip8, JsError> {
+        let mode = match platform {
+            0 => ChipMode::Chip8,
+            1 => ChipMode::SuperChip,
+            2 => ChipMode::XOChip,
+            3 => ChipMode::MegaChip,
+            _ => return Err(JsError::new(&format!("Unknown platform {platform}"))),
+        };
+        let quirks = Quirks::preset_for(&mode);
+        let chip8 = Chip8::new(
+            Rom::from_bytes(bytes),
+            &mode,
+            &quirks,
+            ticks_per_frame,
+            None,
+        )
+        .map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(WasmChip8 {
+            chip8,
+            bitplane: Vec::new(),
+        })
+    }
+
+    /// Executes one frame of instructions and ticks the timers once.
+    pub fn step_frame(&mut self) -> Result<(), JsError> {
+        self.chip8
+            .step_frame()
+            .map(|_| ())
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Screen as RGBA bytes, `width() * height() * 4` long.
+    pub fn framebuffer(&mut self) -> Vec<u8> {
+        let display = self.chip8.display();
+        self.bitplane
+            .resize(display.width() * display.height(), Color::Disabled);
+        display.write_bitplane(&mut self.bitplane);
+        self.bitplane
+            .iter()
+            .flat_map(|color| PALETTE[color.index()])
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.chip8.display().width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.chip8.display().height()
+    }
+
+    /// Presses the keypad key `key`, from 0x0 to 0xF. Other keys are
+    /// ignored.
+    pub fn press_key(&mut self, key: u8) {
+        if key <= 0xF {
+            self.chip8.keyboard_mut().press_key(key);
+        }
+    }
+
+    pub fn release_key(&mut self, key: u8) {
+        if key <= 0xF {
+            self.chip8.keyboard_mut().release_key(key);
+        }
+    }
+
+    /// The page should beep while it is not zero.
+    pub fn sound_timer(&self) -> u8 {
+        self.chip8.sound_timer()
+    }
+
+    /// Whether the program exited with `00FD`.
+    pub fn is_halted(&self) -> bool {
+        self.chip8.is_halted()
+    }
+}
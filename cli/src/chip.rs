@@ -2,14 +2,39 @@ use chip8::chip::Chip8;
 use chip8::platform::{ChipMode, Quirks};
 use chip8::rom::Rom;
 use std::collections::HashSet;
+use std::error::Error;
 
-pub fn init_chip8<'a>(
-    file: &'a str,
-    mode: &'a ChipMode,
-    quirks: &'a HashSet<Quirks>,
+pub fn init_chip8(
+    file: &str,
+    mode: &ChipMode,
+    quirks: &HashSet<Quirks>,
     ticks: u16,
-    sleep: Option<u8>,
-) -> Chip8<'a> {
-    let rom = Rom::new(file);
-    Chip8::new(rom, mode, quirks, ticks as u32, sleep)
+    sleep: Option<u32>,
+    seed: Option<u64>,
+    load_address: u16,
+) -> Result<Chip8, Box<dyn Error>> {
+    let rom = Rom::new(file)?;
+    let mut chip8 = Chip8::with_load_address(rom, mode, quirks, ticks as u32, sleep, load_address)?;
+    if let Some(seed) = seed {
+        chip8.set_seed(seed);
+    }
+    Ok(chip8)
+}
+
+/// Prints the number of executed instructions and their share by mnemonic,
+/// the most frequent first.
+pub fn print_stats(chip8: &Chip8) {
+    let total = chip8.instruction_count();
+    println!("Executed {total} instructions");
+
+    let mut counts = chip8.opcode_counts().iter().collect::<Vec<_>>();
+    counts.sort_by(|(a_name, a_count), (b_name, b_count)| {
+        b_count.cmp(a_count).then(a_name.cmp(b_name))
+    });
+    counts.into_iter().for_each(|(name, count)| {
+        println!(
+            "{name:<5} {:>6.2}% {count:>12}",
+            *count as f64 * 100.0 / total as f64
+        );
+    });
 }
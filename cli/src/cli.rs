@@ -1,2 +1,3 @@
 mod args;
+mod config;
 pub mod parser;
@@ -1,14 +1,28 @@
 use clap::builder::PossibleValue;
 use clap::{Parser, ValueEnum};
 use clap_num::maybe_hex;
+use sdl2::keyboard::Keycode;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 pub struct Args {
     /// Path to CHIP program file.
     pub file: String,
 
+    /// TOML file with the platform, quirks, scale, speed and palette.
+    ///
+    /// Flags given on the command line take precedence over the file.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
     /// The CHIP platform to use.
-    #[arg(short, long, value_enum, default_value_t = Platform::Chip8)]
+    ///
+    /// By default it is guessed from the opcodes the program uses, falling
+    /// back to CHIP-8 when it is unclear.
+    ///
+    /// The quirks of the canonical interpreter of the platform are enabled,
+    /// the quirk flags enable more on top of them.
+    #[arg(short, long, value_enum, default_value_t = Platform::Auto)]
     pub platform: Platform,
 
     /// Quirk for FX55 and FX65 instructions.
@@ -19,9 +33,13 @@ pub struct Args {
     /// would still hold the same value as it did before.
     ///
     /// Specifying this flag will enable CHIP-8 behaviour.
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_load_increment_i_with_x_quirk")]
     pub load_increment_i_with_x_quirk: bool,
 
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "load_increment_i_with_x_quirk")]
+    pub no_load_increment_i_with_x_quirk: bool,
+
     /// Quirk for BNNN instruction.
     ///
     /// CHIP-8 interpreter jumped to the address NNN plus the value in
@@ -30,10 +48,27 @@ pub struct Args {
     /// Starting with CHIP-48 and SUPER-CHIP It will jump to the address
     /// XNN, plus the value in the register VX.
     ///
-    /// Specifying this flag will enable modern behaviour.
-    #[arg(short, long)]
+    /// Specifying this flag will enable modern behaviour. It is the default
+    /// for SUPER-CHIP.
+    #[arg(short, long, overrides_with = "no_jump_using_x_quirk")]
     pub jump_using_x_quirk: bool,
 
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "jump_using_x_quirk")]
+    pub no_jump_using_x_quirk: bool,
+
+    /// Quirk for BNNN instruction.
+    ///
+    /// Specifying this flag will make SUPER-CHIP jump to the address NNN
+    /// plus the value in the register V0, as CHIP-8 does. It takes
+    /// precedence over --jump-using-x-quirk.
+    #[arg(long, overrides_with = "no_jump_using_v0_quirk")]
+    pub jump_using_v0_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "jump_using_v0_quirk")]
+    pub no_jump_using_v0_quirk: bool,
+
     /// Quirk for 8XY6 and 8XYE instructions.
     ///
     /// In the CHIP-8 interpreter, the instruction puts the value of VY
@@ -43,25 +78,133 @@ pub struct Args {
     /// in place and ignored the VY completely.
     ///
     /// Specifying this flag will enable modern behaviour.
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_shift_ignore_vy_quirk")]
     pub shift_ignore_vy_quirk: bool,
 
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "shift_ignore_vy_quirk")]
+    pub no_shift_ignore_vy_quirk: bool,
+
     /// Quirk for 8XY1, 8XY2 and 8XY3 instructions.
     ///
     /// The AND, OR and XOR opcodes reset the VF register to zero in
     /// the end.
     ///
     /// Specifying this flag will reset VF for 8XY1, 8XY2 and 8XY3 instructions.
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_binary_op_reset_vf_quirk")]
     pub binary_op_reset_vf_quirk: bool,
 
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "binary_op_reset_vf_quirk")]
+    pub no_binary_op_reset_vf_quirk: bool,
+
     /// Wraps pixels instead of clipping them.
     ///
     /// When this quirk is enabled, sprites get rendered at the coordinates on
     /// the other side of the screen.
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_wrap_instead_of_clipping_quirk")]
     pub wrap_instead_of_clipping_quirk: bool,
 
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "wrap_instead_of_clipping_quirk")]
+    pub no_wrap_instead_of_clipping_quirk: bool,
+
+    /// Wraps pixels past the right edge only, the bottom edge still clips.
+    #[arg(long, overrides_with = "no_wrap_horizontally_quirk")]
+    pub wrap_horizontally_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "wrap_horizontally_quirk")]
+    pub no_wrap_horizontally_quirk: bool,
+
+    /// Wraps pixels past the bottom edge only, the right edge still clips.
+    #[arg(long, overrides_with = "no_wrap_vertically_quirk")]
+    pub wrap_vertically_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "wrap_vertically_quirk")]
+    pub no_wrap_vertically_quirk: bool,
+
+    /// Quirk for FX1E instruction.
+    ///
+    /// The Amiga interpreter set VF to 1 when I overflowed past the
+    /// addressable memory, and to 0 otherwise.
+    ///
+    /// Specifying this flag will enable Amiga behaviour.
+    #[arg(short, long, overrides_with = "no_add_i_overflow_sets_vf_quirk")]
+    pub add_i_overflow_sets_vf_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "add_i_overflow_sets_vf_quirk")]
+    pub no_add_i_overflow_sets_vf_quirk: bool,
+
+    /// Quirk for FX0A instruction.
+    ///
+    /// COSMAC VIP interpreter stored the key in VX only after it was
+    /// released.
+    ///
+    /// Specifying this flag will enable COSMAC VIP behaviour.
+    #[arg(long, overrides_with = "no_wait_for_key_release_quirk")]
+    pub wait_for_key_release_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "wait_for_key_release_quirk")]
+    pub no_wait_for_key_release_quirk: bool,
+
+    /// Quirk for 00FE and 00FF instructions.
+    ///
+    /// SUPER-CHIP keeps the screen content when the resolution is
+    /// switched, while XO-CHIP clears it.
+    ///
+    /// Specifying this flag will clear the screen on resolution switch.
+    #[arg(short, long, overrides_with = "no_clear_on_mode_switch_quirk")]
+    pub clear_on_mode_switch_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "clear_on_mode_switch_quirk")]
+    pub no_clear_on_mode_switch_quirk: bool,
+
+    /// Quirk for DXYN instruction.
+    ///
+    /// COSMAC VIP interpreter waited for the vertical blank before drawing
+    /// a sprite, so only one sprite was drawn per frame.
+    ///
+    /// Specifying this flag will end the frame after each sprite, leaving
+    /// the rest of --instructions-per-frame unused.
+    #[arg(short, long, overrides_with = "no_display_wait_quirk")]
+    pub display_wait_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "display_wait_quirk")]
+    pub no_display_wait_quirk: bool,
+
+    /// Quirk for 00CN, 00FB and 00FC instructions.
+    ///
+    /// SUPER-CHIP scrolled the low resolution screen by half the distance
+    /// because it is drawn on the high resolution one.
+    ///
+    /// Specifying this flag will enable SUPER-CHIP behaviour.
+    #[arg(long, overrides_with = "no_lores_half_scroll_quirk")]
+    pub lores_half_scroll_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "lores_half_scroll_quirk")]
+    pub no_lores_half_scroll_quirk: bool,
+
+    /// Quirk for DXYN instruction in low resolution.
+    ///
+    /// SUPER-CHIP has only the high resolution screen and draws every low
+    /// resolution pixel as a 2x2 block on it, so the screen is kept when
+    /// the resolution changes.
+    ///
+    /// Specifying this flag will enable SUPER-CHIP behaviour.
+    #[arg(long, overrides_with = "no_lores_wide_sprites_quirk")]
+    pub lores_wide_sprites_quirk: bool,
+
+    /// Disables the quirk, e.g. when the platform enables it.
+    #[arg(long, overrides_with = "lores_wide_sprites_quirk")]
+    pub no_lores_wide_sprites_quirk: bool,
+
     /// Scale of the emulator window.
     #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u8).range(..=13))]
     pub scale: u8,
@@ -72,52 +215,193 @@ pub struct Args {
     #[arg(short, long, default_value_t = 1000)]
     pub instructions_per_frame: u16,
 
+    /// Frames per second the emulation runs at, independent of the display
+    /// refresh rate.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..=1000))]
+    pub fps: u32,
+
     /// Program will wait this amount of microseconds after each instruction.
     ///
     /// Use this if the program is very fast and you want to slow down it.
-    #[arg(long, value_parser = clap::value_parser!(u8))]
-    pub sleep: Option<u8>,
+    /// The wait is made once per frame, so a frame takes at least the sleep
+    /// times --instructions-per-frame microseconds. From 0 to 1000000.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=1_000_000))]
+    pub sleep: Option<u32>,
+
+    /// Keyboard keys bound to the CHIP-8 keypad.
+    ///
+    /// 16 keys, the first one is bound to the CHIP-8 key 0, the second one
+    /// to the key 1 and so on up to the key F. The default binds the
+    /// keypad to the left side of a QWERTY keyboard:
+    ///
+    /// 1 2 3 4  ->  1 2 3 C
+    /// Q W E R  ->  4 5 6 D
+    /// A S D F  ->  7 8 9 E
+    /// Z X C V  ->  A 0 B F
+    #[arg(long, default_value = "x123qweasdzc4rfv", value_parser = parse_keymap)]
+    pub keymap: String,
+
+    /// Record the keypad input of every frame to this file.
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay the keypad input recorded with --record instead of the live
+    /// input. Use it with the same --seed to reproduce the run exactly.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Seed of the random number generator, random by default.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Address the program is loaded at and started from, e.g. 0x600 for
+    /// ETI 660 programs.
+    #[arg(long, value_parser = maybe_hex::<u16>, default_value = "0x200")]
+    pub load_address: u16,
+
+    /// Print how many instructions were executed by mnemonic on exit.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Write the whole memory to this file on exit.
+    #[arg(long, value_name = "FILE")]
+    pub dump_on_exit: Option<PathBuf>,
+
+    /// Key that saves a screenshot of the screen to the working directory.
+    #[arg(long, default_value = "F12", value_parser = parse_keycode)]
+    pub screenshot_key: Keycode,
+
+    /// Key that pauses and resumes the emulation.
+    #[arg(long, default_value = "Space", value_parser = parse_keycode)]
+    pub pause_key: Keycode,
+
+    /// Key that runs the emulation 4 times as fast while it is held.
+    #[arg(long, default_value = "Tab", value_parser = parse_keycode)]
+    pub fast_forward_key: Keycode,
+
+    /// Key that runs the emulation backwards while it is held, one frame per
+    /// frame, as far as --rewind-frames allows.
+    #[arg(long, default_value = "Backspace", value_parser = parse_keycode)]
+    pub rewind_key: Keycode,
+
+    /// Number of the last frames that can be rewound.
+    ///
+    /// Each frame keeps a copy of the machine, about 20KB for CHIP-8 and
+    /// SUPER-CHIP and 80KB for XO-CHIP, so the default 600 frames (10
+    /// seconds) take about 12MB or 48MB. 0 disables rewinding.
+    #[arg(long, value_name = "FRAMES", default_value_t = 600)]
+    pub rewind_frames: usize,
+
+    /// Key that switches the emulation between the normal and half speed.
+    #[arg(long, default_value = "F2", value_parser = parse_keycode)]
+    pub slow_motion_key: Keycode,
+
+    /// Show the registers, timers and FPS over the screen from the start.
+    #[arg(long)]
+    pub debug_overlay: bool,
+
+    /// Key that shows and hides the registers, timers and FPS over the
+    /// screen.
+    #[arg(long, default_value = "F1", value_parser = parse_keycode)]
+    pub debug_overlay_key: Keycode,
+
+    /// Volume of the sound in range 0.0..=1.0.
+    #[arg(long, default_value_t = 0.5, value_parser = parse_volume)]
+    pub volume: f32,
+
+    /// Shape of the sound wave.
+    #[arg(long, value_enum, default_value_t = Waveform::Square)]
+    pub waveform: Waveform,
+
+    /// Frequency in Hz of the beep on CHIP-8 and SUPER-CHIP.
+    ///
+    /// XO-CHIP plays the audio pattern of the program instead.
+    #[arg(long, default_value_t = 440, value_parser = clap::value_parser!(u16).range(20..=8000))]
+    pub beep_hz: u16,
+
+    /// Number of frames a pixel takes to fade out after it was turned off.
+    ///
+    /// Reduces the flicker of sprites that are erased and drawn again every
+    /// frame. 0 turns pixels off instantly.
+    #[arg(long, value_name = "FRAMES", default_value_t = 0)]
+    pub fade: u8,
+
+    /// Color preset for the pixels.
+    ///
+    /// Colors set with `--set-*-color` flags take precedence over the preset.
+    #[arg(long, value_enum, default_value_t = Palette::Default)]
+    pub palette: Palette,
 
     /// Set color in hex for disabled pixels.
-    #[arg(long, default_value = "0x000000", value_parser = maybe_hex::<u32>, value_name = "DISABLED COLOR")]
-    pub set_disabled_color: u32,
+    #[arg(long, value_parser = maybe_hex::<u32>, value_name = "DISABLED COLOR")]
+    pub set_disabled_color: Option<u32>,
 
     /// Set color in hex for enabled pixels on the first plane.
-    #[arg(long, default_value = "0xFF0000", value_parser = maybe_hex::<u32>, value_name = "FIRST PLANE VALUE")]
-    pub set_first_plane_color: u32,
+    #[arg(long, value_parser = maybe_hex::<u32>, value_name = "FIRST PLANE VALUE")]
+    pub set_first_plane_color: Option<u32>,
 
     /// Set color in hex for enabled pixels on the second plane.
-    #[arg(long, default_value = "0x00FF00", value_parser = maybe_hex::<u32>, value_name = "SECOND PLANE VALUE")]
-    pub set_second_plane_color: u32,
+    #[arg(long, value_parser = maybe_hex::<u32>, value_name = "SECOND PLANE VALUE")]
+    pub set_second_plane_color: Option<u32>,
 
     /// Set color in hex for enabled pixels on the first and second plane.
-    #[arg(long, default_value = "0x0000FF", value_parser = maybe_hex::<u32>, value_name = "BOTH PLANE VALUE")]
-    pub set_both_plane_color: u32,
+    #[arg(long, value_parser = maybe_hex::<u32>, value_name = "BOTH PLANE VALUE")]
+    pub set_both_plane_color: Option<u32>,
 }
 
 #[derive(Clone)]
 pub enum Platform {
+    Auto,
     Chip8,
     SuperChip,
     XOChip,
+    MegaChip,
+}
+
+#[derive(Clone)]
+pub enum Palette {
+    Default,
+    ClassicGreen,
+    Amber,
+    Grayscale,
+    Octo,
+    GameBoy,
+}
+
+#[derive(Clone)]
+pub enum Waveform {
+    Square,
+    Sine,
 }
 
 impl ValueEnum for Platform {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Chip8, Self::SuperChip, Self::XOChip]
+        &[
+            Self::Auto,
+            Self::Chip8,
+            Self::SuperChip,
+            Self::XOChip,
+            Self::MegaChip,
+        ]
     }
 
     fn from_str(input: &str, _ignore_case: bool) -> Result<Self, String> {
         match input.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
             "chip8" => Ok(Self::Chip8),
             "superchip" => Ok(Self::SuperChip),
             "xochip" => Ok(Self::XOChip),
+            "megachip" => Ok(Self::MegaChip),
             _ => Err(format!("Invalid platform: {}", input)),
         }
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
         match self {
+            Self::Auto => Some(
+                PossibleValue::new("auto")
+                    .help("Platform is guessed from the instructions the program uses."),
+            ),
             Self::Chip8 => {
                 Some(PossibleValue::new("chip8").help("Program will run only CHIP-8 instructions."))
             }
@@ -129,6 +413,87 @@ impl ValueEnum for Platform {
                 PossibleValue::new("xochip")
                     .help("Program will run only CHIP-8 + SuperChip + XO-Chip instructions."),
             ),
+            Self::MegaChip => Some(
+                PossibleValue::new("mchip")
+                    .help("Program will run CHIP-8 + SuperChip + MegaChip instructions."),
+            ),
+        }
+    }
+}
+
+fn parse_keymap(input: &str) -> Result<String, String> {
+    let keys = input.to_uppercase().chars().collect::<Vec<_>>();
+    if keys.len() != 16 {
+        return Err(format!("Expected 16 keys, got {}", keys.len()));
+    }
+    if let Some(key) = keys.iter().find(|key| !key.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid key: {key}"));
+    }
+    if let Some(key) = keys
+        .iter()
+        .enumerate()
+        .find_map(|(i, key)| keys[..i].contains(key).then_some(key))
+    {
+        return Err(format!("Key {key} is bound more than once"));
+    }
+    Ok(keys.into_iter().collect())
+}
+
+impl ValueEnum for Palette {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Default,
+            Self::ClassicGreen,
+            Self::Amber,
+            Self::Grayscale,
+            Self::Octo,
+            Self::GameBoy,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Default => {
+                Some(PossibleValue::new("default").help("Red, green and blue on black."))
+            }
+            Self::ClassicGreen => {
+                Some(PossibleValue::new("classic-green").help("Green phosphor monitor."))
+            }
+            Self::Amber => Some(PossibleValue::new("amber").help("Amber phosphor monitor.")),
+            Self::Grayscale => Some(PossibleValue::new("grayscale").help("Shades of gray.")),
+            Self::Octo => Some(PossibleValue::new("octo").help("Default colors of Octo.")),
+            Self::GameBoy => Some(PossibleValue::new("gameboy").help("Original Game Boy screen.")),
         }
     }
 }
+
+impl ValueEnum for Waveform {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Square, Self::Sine]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Square => {
+                Some(PossibleValue::new("square").help("Harsh square wave of the original."))
+            }
+            Self::Sine => {
+                Some(PossibleValue::new("sine").help("Square wave smoothed closer to a sine wave."))
+            }
+        }
+    }
+}
+
+fn parse_volume(input: &str) -> Result<f32, String> {
+    let volume = input
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid volume: {input}"))?;
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(format!("Volume {volume} is not in range 0.0..=1.0"));
+    }
+    Ok(volume)
+}
+
+fn parse_keycode(input: &str) -> Result<Keycode, String> {
+    Keycode::from_name(input).ok_or(format!("Invalid key: {input}"))
+}
@@ -0,0 +1,257 @@
+use crate::cli::args::{Args, Palette, Platform};
+use clap::ArgMatches;
+use clap::ValueEnum;
+use clap::parser::ValueSource;
+use serde::{Deserialize, Deserializer};
+use std::fmt::Display;
+use std::path::Path;
+
+/// Settings loaded with `--config`. Every value is optional, the flags
+/// given on the command line take precedence over the file.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    platform: Option<Platform>,
+    scale: Option<u8>,
+    instructions_per_frame: Option<u16>,
+    sleep: Option<u32>,
+    quirks: QuirksConfig,
+    palette: PaletteConfig,
+}
+
+/// Quirks enabled in the config file, named as the quirk flags without
+/// the `-quirk` suffix.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct QuirksConfig {
+    load_increment_i_with_x: Option<bool>,
+    jump_using_x: Option<bool>,
+    jump_using_v0: Option<bool>,
+    shift_ignore_vy: Option<bool>,
+    binary_op_reset_vf: Option<bool>,
+    wrap_instead_of_clipping: Option<bool>,
+    wrap_horizontally: Option<bool>,
+    wrap_vertically: Option<bool>,
+    add_i_overflow_sets_vf: Option<bool>,
+    wait_for_key_release: Option<bool>,
+    clear_on_mode_switch: Option<bool>,
+    display_wait: Option<bool>,
+    lores_half_scroll: Option<bool>,
+    lores_wide_sprites: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct PaletteConfig {
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    preset: Option<Palette>,
+    disabled_color: Option<u32>,
+    first_plane_color: Option<u32>,
+    second_plane_color: Option<u32>,
+    both_plane_color: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file couldn't be read.
+    Io(std::io::Error),
+    /// The config file isn't a valid TOML or has unknown settings.
+    Parse(toml::de::Error),
+}
+
+impl ConfigFile {
+    const SAMPLE: &'static str = r#"platform = "schip"
+scale = 10
+instructions-per-frame = 500
+sleep = 15
+
+[quirks]
+shift-ignore-vy = true
+jump-using-x = true
+
+[palette]
+preset = "octo"
+disabled-color = 0x000000
+first-plane-color = 0xFFCC00"#;
+
+    pub fn load(path: &Path) -> Result<ConfigFile, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        toml::from_str(&content).map_err(ConfigError::Parse)
+    }
+
+    /// Fills `args` with the values of the file, unless they were given on
+    /// the command line. A quirk set to `false` in the file is disabled
+    /// even if the platform enables it.
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) {
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if let Some(platform) = self.platform.filter(|_| !from_command_line("platform")) {
+            args.platform = platform;
+        }
+        if let Some(scale) = self.scale.filter(|_| !from_command_line("scale")) {
+            args.scale = scale;
+        }
+        if let Some(ticks) = self
+            .instructions_per_frame
+            .filter(|_| !from_command_line("instructions_per_frame"))
+        {
+            args.instructions_per_frame = ticks;
+        }
+        if let Some(sleep) = self.sleep.filter(|_| !from_command_line("sleep")) {
+            args.sleep = Some(sleep);
+        }
+
+        let quirks = self.quirks;
+        let given = |quirk: &str| {
+            from_command_line(&format!("{quirk}_quirk"))
+                || from_command_line(&format!("no_{quirk}_quirk"))
+        };
+        apply_quirk(
+            quirks.load_increment_i_with_x,
+            given("load_increment_i_with_x"),
+            &mut args.load_increment_i_with_x_quirk,
+            &mut args.no_load_increment_i_with_x_quirk,
+        );
+        apply_quirk(
+            quirks.jump_using_x,
+            given("jump_using_x"),
+            &mut args.jump_using_x_quirk,
+            &mut args.no_jump_using_x_quirk,
+        );
+        apply_quirk(
+            quirks.jump_using_v0,
+            given("jump_using_v0"),
+            &mut args.jump_using_v0_quirk,
+            &mut args.no_jump_using_v0_quirk,
+        );
+        apply_quirk(
+            quirks.shift_ignore_vy,
+            given("shift_ignore_vy"),
+            &mut args.shift_ignore_vy_quirk,
+            &mut args.no_shift_ignore_vy_quirk,
+        );
+        apply_quirk(
+            quirks.binary_op_reset_vf,
+            given("binary_op_reset_vf"),
+            &mut args.binary_op_reset_vf_quirk,
+            &mut args.no_binary_op_reset_vf_quirk,
+        );
+        apply_quirk(
+            quirks.wrap_instead_of_clipping,
+            given("wrap_instead_of_clipping"),
+            &mut args.wrap_instead_of_clipping_quirk,
+            &mut args.no_wrap_instead_of_clipping_quirk,
+        );
+        apply_quirk(
+            quirks.wrap_horizontally,
+            given("wrap_horizontally"),
+            &mut args.wrap_horizontally_quirk,
+            &mut args.no_wrap_horizontally_quirk,
+        );
+        apply_quirk(
+            quirks.wrap_vertically,
+            given("wrap_vertically"),
+            &mut args.wrap_vertically_quirk,
+            &mut args.no_wrap_vertically_quirk,
+        );
+        apply_quirk(
+            quirks.add_i_overflow_sets_vf,
+            given("add_i_overflow_sets_vf"),
+            &mut args.add_i_overflow_sets_vf_quirk,
+            &mut args.no_add_i_overflow_sets_vf_quirk,
+        );
+        apply_quirk(
+            quirks.wait_for_key_release,
+            given("wait_for_key_release"),
+            &mut args.wait_for_key_release_quirk,
+            &mut args.no_wait_for_key_release_quirk,
+        );
+        apply_quirk(
+            quirks.clear_on_mode_switch,
+            given("clear_on_mode_switch"),
+            &mut args.clear_on_mode_switch_quirk,
+            &mut args.no_clear_on_mode_switch_quirk,
+        );
+        apply_quirk(
+            quirks.display_wait,
+            given("display_wait"),
+            &mut args.display_wait_quirk,
+            &mut args.no_display_wait_quirk,
+        );
+        apply_quirk(
+            quirks.lores_half_scroll,
+            given("lores_half_scroll"),
+            &mut args.lores_half_scroll_quirk,
+            &mut args.no_lores_half_scroll_quirk,
+        );
+        apply_quirk(
+            quirks.lores_wide_sprites,
+            given("lores_wide_sprites"),
+            &mut args.lores_wide_sprites_quirk,
+            &mut args.no_lores_wide_sprites_quirk,
+        );
+
+        let palette = self.palette;
+        if let Some(preset) = palette.preset.filter(|_| !from_command_line("palette")) {
+            args.palette = preset;
+        }
+        args.set_disabled_color = args.set_disabled_color.or(palette.disabled_color);
+        args.set_first_plane_color = args.set_first_plane_color.or(palette.first_plane_color);
+        args.set_second_plane_color = args.set_second_plane_color.or(palette.second_plane_color);
+        args.set_both_plane_color = args.set_both_plane_color.or(palette.both_plane_color);
+    }
+}
+
+/// Enables or disables the quirk as the file says, unless it was `given`
+/// on the command line.
+fn apply_quirk(value: Option<bool>, given: bool, enable: &mut bool, disable: &mut bool) {
+    if let Some(value) = value.filter(|_| !given) {
+        *enable = value;
+        *disable = !value;
+    }
+}
+
+/// Reads a value by the name it has on the command line, e.g. `schip`.
+fn deserialize_value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    T::value_variants()
+        .iter()
+        .find(|variant| {
+            variant
+                .to_possible_value()
+                .is_some_and(|value| value.matches(&name, true))
+        })
+        .cloned()
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid value \"{name}\"")))
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "Unable to read config file: {err}"),
+            ConfigError::Parse(err) => write!(
+                f,
+                "Invalid config file: {err}\nExpected a TOML file like:\n\n{}",
+                ConfigFile::SAMPLE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+        }
+    }
+}
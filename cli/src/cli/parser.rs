@@ -1,8 +1,13 @@
-use crate::cli::args::{Args, Platform};
-use chip8::display::Color;
+use crate::cli::args::{Args, Palette, Platform, Waveform};
+use crate::cli::config::ConfigFile;
+use crate::devices::audio;
+use crate::devices::keyboard::Hotkey;
 use chip8::platform::{ChipMode, Quirks};
-use clap::Parser;
+use chip8::rom::Rom;
+use clap::{CommandFactory, FromArgMatches};
+use sdl2::keyboard::Keycode;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 pub struct EmulatorConfig {
     pub file: String,
@@ -10,72 +15,212 @@ pub struct EmulatorConfig {
     pub mode: ChipMode,
     pub scale: u8,
     pub ticks: u16,
-    pub sleep: Option<u8>,
-    pub palette: HashMap<Color, (u8, u8, u8)>,
+    pub sleep: Option<u32>,
+    pub fps: u32,
+    pub seed: Option<u64>,
+    pub load_address: u16,
+    pub stats: bool,
+    pub dump_on_exit: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub keymap: String,
+    pub hotkeys: HashMap<Keycode, Hotkey>,
+    pub fast_forward_key: Keycode,
+    pub rewind_key: Keycode,
+    pub rewind_frames: usize,
+    pub debug_overlay: bool,
+    pub volume: f32,
+    pub waveform: audio::Waveform,
+    pub beep_hz: u16,
+    /// Colors indexed by `Color as usize`.
+    pub palette: [(u8, u8, u8); 4],
+    pub fade: u8,
 }
 
 impl EmulatorConfig {
     pub fn new() -> EmulatorConfig {
-        let args = Args::parse();
-        let mut quirks = HashSet::new();
-
-        if args.load_increment_i_with_x_quirk {
-            quirks.insert(Quirks::IRegisterIncrementedWithX);
-        }
-        if args.jump_using_x_quirk {
-            quirks.insert(Quirks::JumpWithX);
-        }
-        if args.shift_ignore_vy_quirk {
-            quirks.insert(Quirks::ShiftIgnoreVY);
-        }
-        if args.binary_op_reset_vf_quirk {
-            quirks.insert(Quirks::BinaryOpResetVF);
-        }
-        if args.wrap_instead_of_clipping_quirk {
-            quirks.insert(Quirks::WrapsInsteadClipping);
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        if let Some(path) = args.config.clone() {
+            match ConfigFile::load(&path) {
+                Ok(config_file) => config_file.apply(&mut args, &matches),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            }
         }
+        let mode = Self::get_chip_mode(&args.platform, &args.file);
+        // The quirk flags enable or disable quirks on top of the preset.
+        let mut quirks = Quirks::preset_for(&mode);
+        [
+            (
+                Quirks::IRegisterIncrementedWithX,
+                args.load_increment_i_with_x_quirk,
+                args.no_load_increment_i_with_x_quirk,
+            ),
+            (
+                Quirks::JumpWithX,
+                args.jump_using_x_quirk,
+                args.no_jump_using_x_quirk,
+            ),
+            (
+                Quirks::JumpWithV0,
+                args.jump_using_v0_quirk,
+                args.no_jump_using_v0_quirk,
+            ),
+            (
+                Quirks::ShiftIgnoreVY,
+                args.shift_ignore_vy_quirk,
+                args.no_shift_ignore_vy_quirk,
+            ),
+            (
+                Quirks::BinaryOpResetVF,
+                args.binary_op_reset_vf_quirk,
+                args.no_binary_op_reset_vf_quirk,
+            ),
+            (
+                Quirks::WrapsInsteadClipping,
+                args.wrap_instead_of_clipping_quirk,
+                args.no_wrap_instead_of_clipping_quirk,
+            ),
+            (
+                Quirks::WrapsHorizontally,
+                args.wrap_horizontally_quirk,
+                args.no_wrap_horizontally_quirk,
+            ),
+            (
+                Quirks::WrapsVertically,
+                args.wrap_vertically_quirk,
+                args.no_wrap_vertically_quirk,
+            ),
+            (
+                Quirks::AddIOverflowSetsVF,
+                args.add_i_overflow_sets_vf_quirk,
+                args.no_add_i_overflow_sets_vf_quirk,
+            ),
+            (
+                Quirks::WaitForKeyRelease,
+                args.wait_for_key_release_quirk,
+                args.no_wait_for_key_release_quirk,
+            ),
+            (
+                Quirks::ClearOnModeSwitch,
+                args.clear_on_mode_switch_quirk,
+                args.no_clear_on_mode_switch_quirk,
+            ),
+            (
+                Quirks::DisplayWait,
+                args.display_wait_quirk,
+                args.no_display_wait_quirk,
+            ),
+            (
+                Quirks::LoresHalfScroll,
+                args.lores_half_scroll_quirk,
+                args.no_lores_half_scroll_quirk,
+            ),
+            (
+                Quirks::LoresWideSprites,
+                args.lores_wide_sprites_quirk,
+                args.no_lores_wide_sprites_quirk,
+            ),
+        ]
+        .into_iter()
+        .for_each(|(quirk, enable, disable)| {
+            if enable {
+                quirks.insert(quirk);
+            } else if disable {
+                quirks.remove(&quirk);
+            }
+        });
 
+        let palette = Self::get_palette(&args);
         EmulatorConfig {
             file: args.file,
-            mode: Self::get_chip_mode(&args.platform),
+            mode,
             scale: args.scale,
             ticks: args.instructions_per_frame,
             sleep: args.sleep,
-            palette: HashMap::from([
-                (Color::Disabled, {
-                    let red = (args.set_disabled_color >> 16) as u8;
-                    let green = (args.set_disabled_color >> 8) as u8;
-                    let blue = args.set_disabled_color as u8;
-                    (red, green, blue)
-                }),
-                (Color::OnlyFirstPlane, {
-                    let red = (args.set_first_plane_color >> 16) as u8;
-                    let green = (args.set_first_plane_color >> 8) as u8;
-                    let blue = args.set_first_plane_color as u8;
-                    (red, green, blue)
-                }),
-                (Color::OnlySecondPlane, {
-                    let red = (args.set_second_plane_color >> 16) as u8;
-                    let green = (args.set_second_plane_color >> 8) as u8;
-                    let blue = args.set_second_plane_color as u8;
-                    (red, green, blue)
-                }),
-                (Color::Both, {
-                    let red = (args.set_both_plane_color >> 16) as u8;
-                    let green = (args.set_both_plane_color >> 8) as u8;
-                    let blue = args.set_both_plane_color as u8;
-                    (red, green, blue)
-                }),
+            fps: args.fps,
+            seed: args.seed,
+            load_address: args.load_address,
+            stats: args.stats,
+            dump_on_exit: args.dump_on_exit,
+            record: args.record,
+            replay: args.replay,
+            keymap: args.keymap,
+            hotkeys: HashMap::from([
+                (args.screenshot_key, Hotkey::Screenshot),
+                (args.pause_key, Hotkey::Pause),
+                (args.debug_overlay_key, Hotkey::DebugOverlay),
+                (args.slow_motion_key, Hotkey::SlowMotion),
             ]),
+            fast_forward_key: args.fast_forward_key,
+            rewind_key: args.rewind_key,
+            rewind_frames: args.rewind_frames,
+            debug_overlay: args.debug_overlay,
+            volume: args.volume,
+            waveform: Self::get_waveform(&args.waveform),
+            beep_hz: args.beep_hz,
+            palette,
+            fade: args.fade,
             quirks,
         }
     }
 
-    fn get_chip_mode(platform: &Platform) -> ChipMode {
+    fn get_chip_mode(platform: &Platform, file: &str) -> ChipMode {
         match platform {
+            Platform::Auto => {
+                // A ROM that can't be read is reported when it is loaded.
+                match Rom::new(file).ok().and_then(|rom| rom.detect_platform()) {
+                    Some(mode) => {
+                        println!("Detected {mode} platform");
+                        mode
+                    }
+                    None => {
+                        println!("Unable to detect the platform, falling back to CHIP-8");
+                        ChipMode::Chip8
+                    }
+                }
+            }
             Platform::Chip8 => ChipMode::Chip8,
             Platform::SuperChip => ChipMode::SuperChip,
             Platform::XOChip => ChipMode::XOChip,
+            Platform::MegaChip => ChipMode::MegaChip,
         }
     }
+
+    fn get_waveform(waveform: &Waveform) -> audio::Waveform {
+        match waveform {
+            Waveform::Square => audio::Waveform::Square,
+            Waveform::Sine => audio::Waveform::Sine,
+        }
+    }
+
+    /// Builds the palette from the preset, overridden by the colors that
+    /// were set explicitly.
+    fn get_palette(args: &Args) -> [(u8, u8, u8); 4] {
+        let [disabled, first_plane, second_plane, both_planes] = match args.palette {
+            Palette::Default => [0x000000, 0xFF0000, 0x00FF00, 0x0000FF],
+            Palette::ClassicGreen => [0x0A1A0A, 0x33FF33, 0x1A991A, 0x99FF99],
+            Palette::Amber => [0x1A1000, 0xFFB000, 0x996A00, 0xFFD780],
+            Palette::Grayscale => [0x000000, 0xFFFFFF, 0x555555, 0xAAAAAA],
+            Palette::Octo => [0x996600, 0xFFCC00, 0xFF6600, 0x662200],
+            Palette::GameBoy => [0x0F380F, 0x9BBC0F, 0x306230, 0x8BAC0F],
+        };
+
+        [
+            Self::to_rgb(args.set_disabled_color.unwrap_or(disabled)),
+            Self::to_rgb(args.set_first_plane_color.unwrap_or(first_plane)),
+            Self::to_rgb(args.set_second_plane_color.unwrap_or(second_plane)),
+            Self::to_rgb(args.set_both_plane_color.unwrap_or(both_planes)),
+        ]
+    }
+
+    fn to_rgb(color: u32) -> (u8, u8, u8) {
+        let red = (color >> 16) as u8;
+        let green = (color >> 8) as u8;
+        let blue = color as u8;
+        (red, green, blue)
+    }
 }
@@ -1,3 +1,5 @@
 pub mod audio;
 pub mod display;
 pub mod keyboard;
+pub mod overlay;
+pub mod recording;
@@ -3,6 +3,15 @@ use sdl2::audio::{AudioCallback, AudioDevice as AudioDeviceSDL, AudioSpecDesired
 
 pub struct AudioDevice {
     subsystem: AudioDeviceSDL<ChipAudio>,
+    /// Frequency of the fixed tone played instead of the audio pattern.
+    beep_hz: Option<u16>,
+}
+
+pub enum Waveform {
+    /// Plays the pattern buffer as is.
+    Square,
+    /// Smooths the square wave with a low-pass filter.
+    Sine,
 }
 
 struct ChipAudio {
@@ -10,6 +19,16 @@ struct ChipAudio {
     pitch: u16,
     phase: f64,
     sample_rate: f64,
+    volume: f32,
+    waveform: Waveform,
+    /// The previous output sample, used by the low-pass filter.
+    last_sample: f32,
+    /// Gain applied to the output, it moves towards `target_gain` by
+    /// `gain_step` per sample, so beeps fade in and out without clicks.
+    gain: f32,
+    /// 1.0 while beeping, 0.0 otherwise.
+    target_gain: f32,
+    gain_step: f32,
 }
 
 impl AudioCallback for ChipAudio {
@@ -21,7 +40,21 @@ impl AudioCallback for ChipAudio {
             let current_byte = self.pattern[pattern_index];
             let bit_value = (current_byte >> (7 - (self.phase as usize % 8))) & 1;
 
-            *sample = if bit_value == 1 { 0.5 } else { -0.5 };
+            let square = if bit_value == 1 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.last_sample = match self.waveform {
+                Waveform::Square => square,
+                Waveform::Sine => self.last_sample + (square - self.last_sample) * 0.1,
+            };
+            self.gain = if self.gain < self.target_gain {
+                (self.gain + self.gain_step).min(self.target_gain)
+            } else {
+                (self.gain - self.gain_step).max(self.target_gain)
+            };
+            *sample = self.last_sample * self.gain;
 
             self.phase += (self.pitch as f64) / self.sample_rate * 128.0;
             if self.phase >= 128.0 {
@@ -32,7 +65,17 @@ impl AudioCallback for ChipAudio {
 }
 
 impl AudioDevice {
-    pub fn new(sdl: &Sdl) -> AudioDevice {
+    /// A square wave with the period of one byte, so the pattern is played
+    /// at 8 times the beep frequency.
+    const BEEP_PATTERN: [u8; 16] = [0xF0; 16];
+    /// Seconds the sound takes to fade in when a beep starts and to fade
+    /// out when it stops.
+    const RAMP_SECONDS: f64 = 0.005;
+
+    /// `volume` is the amplitude of the output in `0.0..=1.0`. With
+    /// `beep_hz` a fixed tone of this frequency is played instead of the
+    /// audio pattern, as CHIP-8 and SUPER-CHIP have no pattern buffer.
+    pub fn new(sdl: &Sdl, volume: f32, waveform: Waveform, beep_hz: Option<u16>) -> AudioDevice {
         let audio_subsystem = sdl.audio().unwrap();
         let desired_spec = AudioSpecDesired {
             freq: Some(44100),
@@ -45,10 +88,22 @@ impl AudioDevice {
                 phase: 0.0,
                 sample_rate: spec.freq as f64,
                 pitch: 0,
+                volume,
+                waveform,
+                last_sample: 0.0,
+                gain: 0.0,
+                target_gain: 0.0,
+                gain_step: (1.0 / (spec.freq as f64 * Self::RAMP_SECONDS)) as f32,
             })
             .unwrap();
+        // The device plays silence between beeps, pausing it would cut the
+        // fade out.
+        device.resume();
 
-        AudioDevice { subsystem: device }
+        AudioDevice {
+            subsystem: device,
+            beep_hz,
+        }
     }
 
     pub fn configure(&mut self, audio_buffer: &[u8], pitch: u16) {
@@ -57,12 +112,16 @@ impl AudioDevice {
         audio_lock.pitch = pitch;
     }
 
-    pub fn play_sound(&mut self, sound_register: u8, audio_buffer: &[u8], pitch: u16) {
-        if sound_register > 0 {
-            self.configure(audio_buffer, pitch);
-            self.subsystem.resume();
-        } else {
-            self.subsystem.pause();
+    /// Fades the sound in while `is_beeping` and out otherwise. The pattern
+    /// and pitch are only updated while beeping, so the fade out plays the
+    /// last ones.
+    pub fn play_sound(&mut self, is_beeping: bool, audio_buffer: &[u8], pitch: u16) {
+        if is_beeping {
+            match self.beep_hz {
+                Some(beep_hz) => self.configure(&Self::BEEP_PATTERN, beep_hz * 8),
+                None => self.configure(audio_buffer, pitch),
+            }
         }
+        self.subsystem.lock().target_gain = if is_beeping { 1.0 } else { 0.0 };
     }
 }
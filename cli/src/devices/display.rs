@@ -1,9 +1,12 @@
+use crate::devices::overlay::DebugOverlay;
 use chip8::display::{Color, Display};
+use image::{ImageResult, Rgb, RgbImage};
 use sdl2::Sdl;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::render::{TextureCreator, WindowCanvas};
 use sdl2::video::WindowContext;
-use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct DisplayDevice {
     texture_creator: TextureCreator<WindowContext>,
@@ -11,11 +14,29 @@ pub struct DisplayDevice {
     canvas: WindowCanvas,
     width: u32,
     height: u32,
-    palette: HashMap<Color, (u8, u8, u8)>,
+    scale: u32,
+    /// Colors indexed by `Color as usize`.
+    palette: [(u8, u8, u8); 4],
+    /// Whether the presented frame has the debug overlay, so it is drawn
+    /// again without it once the overlay is hidden.
+    has_overlay: bool,
 }
 
+/// Buffers of the current resolution, `width * height` pixels.
 struct Frame {
-    pixels: [u8; 24576],
+    /// Reused on every update to avoid building a new bitplane per frame.
+    bitplane: Vec<Color>,
+    /// RGB of every pixel.
+    pixels: Vec<u8>,
+    /// Number of frames a turned off pixel takes to fade out.
+    fade: u8,
+    /// Frames left until the pixel fades out completely, `fade` while it is
+    /// turned on.
+    intensity: Vec<u8>,
+    /// Color of the pixel when it was turned on the last time.
+    lit_color: Vec<(u8, u8, u8)>,
+    /// Whether any pixel is still fading out.
+    is_fading: bool,
 }
 
 impl DisplayDevice {
@@ -25,7 +46,8 @@ impl DisplayDevice {
         width: u32,
         height: u32,
         scale: u32,
-        palette: HashMap<Color, (u8, u8, u8)>,
+        palette: [(u8, u8, u8); 4],
+        fade: u8,
     ) -> DisplayDevice {
         let window = sdl_context
             .video()
@@ -35,7 +57,9 @@ impl DisplayDevice {
             .build()
             .unwrap();
 
-        let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+        // Frames are paced by the emulator, vsync would tie them to the
+        // refresh rate of the display.
+        let mut canvas = window.into_canvas().build().unwrap();
         canvas.set_scale(scale as f32, scale as f32).unwrap();
         let texture_creator = canvas.texture_creator();
 
@@ -43,15 +67,30 @@ impl DisplayDevice {
             texture_creator,
             width,
             height,
+            scale,
             canvas,
             palette,
-            current_frame: Frame::default(),
+            current_frame: Frame::new(fade, (width * height) as usize),
+            has_overlay: false,
         }
     }
 
-    pub fn draw(&mut self, display: &Display) {
-        self.height = display.height() as u32;
-        self.width = display.width() as u32;
+    /// Uploads and presents the screen with the `overlay` lines over it.
+    /// Does nothing if nothing changed since the last draw and no pixel is
+    /// fading out.
+    pub fn draw(&mut self, display: &Display, overlay: Option<&[String]>) {
+        if !display.is_dirty()
+            && !self.current_frame.is_fading
+            && overlay.is_none()
+            && !self.has_overlay
+        {
+            return;
+        }
+        let (width, height) = display.resolution();
+        let (width, height) = (width as u32, height as u32);
+        if (width, height) != (self.width, self.height) {
+            self.resize(width, height);
+        }
 
         let mut texture = self
             .texture_creator
@@ -64,31 +103,114 @@ impl DisplayDevice {
             .unwrap();
 
         self.canvas.copy(&texture, None, None).unwrap();
+        if let Some(lines) = overlay {
+            Self::draw_overlay(&mut self.canvas, self.scale, lines);
+        }
+        self.has_overlay = overlay.is_some();
         self.canvas.present();
     }
+
+    /// Draws the overlay in window pixels, its font pixel is a third of the
+    /// screen pixel.
+    fn draw_overlay(canvas: &mut WindowCanvas, scale: u32, lines: &[String]) {
+        canvas.set_scale(1.0, 1.0).unwrap();
+        DebugOverlay::draw(canvas, lines, (scale as i32 / 3).max(1));
+        canvas.set_scale(scale as f32, scale as f32).unwrap();
+    }
+
+    /// Resizes the window to the new resolution keeping the scale.
+    fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        // Pixels of the old resolution don't map onto the new one.
+        self.current_frame = Frame::new(self.current_frame.fade, (width * height) as usize);
+        if let Err(err) = self
+            .canvas
+            .window_mut()
+            .set_size(width * self.scale, height * self.scale)
+        {
+            eprintln!("Unable to resize the window: {err}");
+        }
+    }
+
+    /// Saves the current screen at its native resolution as
+    /// `screenshot-<timestamp>.png` in the working directory.
+    pub fn screenshot(&self, display: &Display) -> ImageResult<PathBuf> {
+        let (width, height) = display.resolution();
+        let width = width as u32;
+        let bitplane = display.display_bitplane();
+        let image = RgbImage::from_fn(width, height as u32, |x, y| {
+            let (red, green, blue) = self.palette[bitplane[(x + y * width) as usize] as usize];
+            Rgb([red, green, blue])
+        });
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = PathBuf::from(format!("screenshot-{timestamp}.png"));
+        image.save(&path)?;
+        Ok(path)
+    }
 }
 
 impl Frame {
-    fn update(&mut self, display: &Display, palette: &HashMap<Color, (u8, u8, u8)>) {
-        display
-            .display_bitplane()
+    fn new(fade: u8, len: usize) -> Frame {
+        Frame {
+            bitplane: vec![Color::Disabled; len],
+            pixels: vec![0; len * 3],
+            fade,
+            intensity: vec![0; len],
+            lit_color: vec![(0, 0, 0); len],
+            is_fading: false,
+        }
+    }
+
+    fn update(&mut self, display: &Display, palette: &[(u8, u8, u8); 4]) {
+        display.write_bitplane(&mut self.bitplane);
+        let disabled = palette[Color::Disabled as usize];
+        self.is_fading = false;
+        self.bitplane
             .iter()
             .enumerate()
-            .for_each(|(pixel, color)| {
-                let rgb = &palette[color];
+            .for_each(|(pixel, &color)| {
+                let rgb = if color != Color::Disabled {
+                    self.intensity[pixel] = self.fade;
+                    self.lit_color[pixel] = palette[color as usize];
+                    palette[color as usize]
+                } else if self.intensity[pixel] > 0 {
+                    let rgb = Self::blend(
+                        disabled,
+                        self.lit_color[pixel],
+                        self.intensity[pixel],
+                        self.fade + 1,
+                    );
+                    self.intensity[pixel] -= 1;
+                    self.is_fading = true;
+                    rgb
+                } else {
+                    disabled
+                };
                 self.pixels[pixel * 3] = rgb.0;
                 self.pixels[pixel * 3 + 1] = rgb.1;
                 self.pixels[pixel * 3 + 2] = rgb.2;
             });
     }
 
-    fn pixels(&self) -> &[u8] {
-        &self.pixels
+    /// Interpolates from `from` to `to` by `step / steps`.
+    fn blend(from: (u8, u8, u8), to: (u8, u8, u8), step: u8, steps: u8) -> (u8, u8, u8) {
+        let channel = |from: u8, to: u8| {
+            let (from, to) = (from as i32, to as i32);
+            (from + (to - from) * step as i32 / steps as i32) as u8
+        };
+        (
+            channel(from.0, to.0),
+            channel(from.1, to.1),
+            channel(from.2, to.2),
+        )
     }
-}
 
-impl Default for Frame {
-    fn default() -> Self {
-        Frame { pixels: [0; 24576] }
+    fn pixels(&self) -> &[u8] {
+        &self.pixels
     }
 }
@@ -1,79 +1,74 @@
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Scancode};
 use sdl2::{EventPump, Sdl};
 use std::collections::HashMap;
 
 pub struct KeyboardDevice {
     event_pump: EventPump,
     keymap: HashMap<Keycode, u8>,
+    hotkeys: HashMap<Keycode, Hotkey>,
+    /// Hotkeys pressed since the last `take_hotkeys` call.
+    pressed_hotkeys: Vec<Hotkey>,
+    /// Set when the window is closed or Escape is pressed.
+    quit_requested: bool,
 }
 
-impl KeyboardDevice {
-    const NUM_1_CODE: u8 = 1;
-    const NUM_2_CODE: u8 = 2;
-    const NUM_3_CODE: u8 = 3;
-    const NUM_4_CODE: u8 = 0xC;
-    const NUM_Q_CODE: u8 = 4;
-    const NUM_W_CODE: u8 = 5;
-    const NUM_E_CODE: u8 = 6;
-    const NUM_R_CODE: u8 = 0xD;
-    const NUM_A_CODE: u8 = 7;
-    const NUM_S_CODE: u8 = 8;
-    const NUM_D_CODE: u8 = 9;
-    const NUM_F_CODE: u8 = 0xE;
-    const NUM_Z_CODE: u8 = 0xA;
-    const NUM_X_CODE: u8 = 0;
-    const NUM_C_CODE: u8 = 0xB;
-    const NUM_V_CODE: u8 = 0xF;
+/// Emulator actions bound to keyboard keys.
+#[derive(Clone, Copy)]
+pub enum Hotkey {
+    Screenshot,
+    Pause,
+    DebugOverlay,
+    SlowMotion,
+}
 
-    pub fn new(sdl_context: &Sdl) -> KeyboardDevice {
+impl KeyboardDevice {
+    /// `keymap` holds 16 keyboard keys, the key at index `i` is bound to
+    /// the CHIP-8 key `i`.
+    pub fn new(
+        sdl_context: &Sdl,
+        keymap: &str,
+        hotkeys: HashMap<Keycode, Hotkey>,
+    ) -> KeyboardDevice {
         let event_pump = sdl_context.event_pump().unwrap();
-        let mut keymap = HashMap::new();
-
-        keymap.insert(Keycode::NUM_1, Self::NUM_1_CODE);
-        keymap.insert(Keycode::NUM_2, Self::NUM_2_CODE);
-        keymap.insert(Keycode::NUM_3, Self::NUM_3_CODE);
-        keymap.insert(Keycode::NUM_4, Self::NUM_4_CODE);
-
-        keymap.insert(Keycode::Q, Self::NUM_Q_CODE);
-        keymap.insert(Keycode::W, Self::NUM_W_CODE);
-        keymap.insert(Keycode::E, Self::NUM_E_CODE);
-        keymap.insert(Keycode::R, Self::NUM_R_CODE);
-
-        keymap.insert(Keycode::A, Self::NUM_A_CODE);
-        keymap.insert(Keycode::S, Self::NUM_S_CODE);
-        keymap.insert(Keycode::D, Self::NUM_D_CODE);
-        keymap.insert(Keycode::F, Self::NUM_F_CODE);
-
-        keymap.insert(Keycode::Z, Self::NUM_Z_CODE);
-        keymap.insert(Keycode::X, Self::NUM_X_CODE);
-        keymap.insert(Keycode::C, Self::NUM_C_CODE);
-        keymap.insert(Keycode::V, Self::NUM_V_CODE);
+        let keymap = keymap
+            .chars()
+            .enumerate()
+            .filter_map(|(chip_key, key)| {
+                Keycode::from_name(&key.to_string()).map(|keycode| (keycode, chip_key as u8))
+            })
+            .collect();
 
-        KeyboardDevice { event_pump, keymap }
+        KeyboardDevice {
+            event_pump,
+            keymap,
+            hotkeys,
+            pressed_hotkeys: vec![],
+            quit_requested: false,
+        }
     }
 
     pub fn keys_state(&mut self) -> [bool; 16] {
         let mut keys_state = [false; 16];
 
-        if let Some(_) = self
-            .event_pump
-            .poll_iter()
-            .filter(|event| {
-                if let Event::Quit { .. }
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
-                } = event
-                {
-                    true
-                } else {
-                    false
+                } => self.quit_requested = true,
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(&hotkey) = self.hotkeys.get(&keycode) {
+                        self.pressed_hotkeys.push(hotkey);
+                    }
                 }
-            })
-            .next()
-        {
-            std::process::exit(0)
+                _ => {}
+            }
         }
 
         self.event_pump
@@ -88,4 +83,23 @@ impl KeyboardDevice {
 
         keys_state
     }
+
+    /// Whether `keycode` is held down at the moment.
+    pub fn is_key_held(&self, keycode: Keycode) -> bool {
+        Scancode::from_keycode(keycode).is_some_and(|scancode| {
+            self.event_pump
+                .keyboard_state()
+                .is_scancode_pressed(scancode)
+        })
+    }
+
+    /// Whether the user asked to close the emulator.
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    /// Returns hotkeys pressed since the last call.
+    pub fn take_hotkeys(&mut self) -> Vec<Hotkey> {
+        std::mem::take(&mut self.pressed_hotkeys)
+    }
 }
@@ -0,0 +1,134 @@
+use chip8::chip::Chip8;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+use std::time::{Duration, Instant};
+
+/// Registers, timers and FPS drawn over the screen for debugging.
+pub struct DebugOverlay {
+    is_visible: bool,
+    /// Frames counted since `second_start`.
+    frames: u32,
+    /// Frames counted during the previous second.
+    fps: u32,
+    second_start: Instant,
+}
+
+impl DebugOverlay {
+    /// Width and height of a glyph in font pixels.
+    const GLYPH_WIDTH: i32 = 3;
+    const GLYPH_HEIGHT: i32 = 5;
+
+    pub fn new(is_visible: bool) -> DebugOverlay {
+        DebugOverlay {
+            is_visible,
+            frames: 0,
+            fps: 0,
+            second_start: Instant::now(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    /// Counts the frame and returns the lines to draw, if the overlay is
+    /// visible.
+    pub fn update(&mut self, chip8: &Chip8) -> Option<Vec<String>> {
+        self.frames += 1;
+        if self.second_start.elapsed() >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.frames = 0;
+            self.second_start = Instant::now();
+        }
+        if !self.is_visible {
+            return None;
+        }
+
+        let registers = |range: std::ops::Range<u8>| {
+            range
+                .map(|x| format!("{:02X}", chip8.register(x)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        Some(vec![
+            format!("PC {:04X}  I {:04X}", chip8.pc(), chip8.i()),
+            format!(
+                "DT {:02X}  ST {:02X}  FPS {}",
+                chip8.delay_timer(),
+                chip8.sound_timer(),
+                self.fps
+            ),
+            format!("V0-7 {}", registers(0..8)),
+            format!("V8-F {}", registers(8..16)),
+        ])
+    }
+
+    /// Draws `lines` on a dark background in the top left corner, each font
+    /// pixel is `pixel_size` window pixels. The canvas must not be scaled.
+    pub fn draw(canvas: &mut WindowCanvas, lines: &[String], pixel_size: i32) {
+        let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i32;
+        let background = Rect::new(
+            0,
+            0,
+            ((columns * (Self::GLYPH_WIDTH + 1) + 1) * pixel_size) as u32,
+            ((lines.len() as i32 * (Self::GLYPH_HEIGHT + 1) + 1) * pixel_size) as u32,
+        );
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 0xC0));
+        canvas.fill_rect(background).unwrap();
+        canvas.set_blend_mode(BlendMode::None);
+
+        let mut rects = vec![];
+        lines.iter().enumerate().for_each(|(row, line)| {
+            let top = (row as i32 * (Self::GLYPH_HEIGHT + 1) + 1) * pixel_size;
+            line.chars().enumerate().for_each(|(column, char)| {
+                let left = (column as i32 * (Self::GLYPH_WIDTH + 1) + 1) * pixel_size;
+                Self::glyph(char).iter().enumerate().for_each(|(y, bits)| {
+                    (0..Self::GLYPH_WIDTH)
+                        .filter(|x| bits >> (Self::GLYPH_WIDTH - 1 - x) & 1 == 1)
+                        .for_each(|x| {
+                            rects.push(Rect::new(
+                                left + x * pixel_size,
+                                top + y as i32 * pixel_size,
+                                pixel_size as u32,
+                                pixel_size as u32,
+                            ))
+                        });
+                });
+            });
+        });
+        canvas.set_draw_color(Color::WHITE);
+        canvas.fill_rects(&rects).unwrap();
+    }
+
+    /// 3x5 glyph of `char`, each row is 3 bits. Unknown characters are
+    /// blank.
+    fn glyph(char: char) -> [u8; 5] {
+        match char {
+            '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+            '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+            '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+            '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+            '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+            '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+            '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+            '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+            '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+            '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+            'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+            'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+            'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+            'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+            'E' => [0b111, 0b100, 0b111, 0b100, 0b111],
+            'F' => [0b111, 0b100, 0b111, 0b100, 0b100],
+            'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+            'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+            'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+            'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+            'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+            '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+            _ => [0; 5],
+        }
+    }
+}
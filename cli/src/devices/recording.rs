@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Writes the keypad state of every frame to a file, 2 bytes per frame
+/// where the bit `i` is set when the CHIP-8 key `i` is pressed.
+pub struct InputRecorder {
+    file: File,
+}
+
+/// Plays back the keypad state recorded by `InputRecorder`.
+pub struct InputReplay {
+    frames: Vec<u16>,
+    position: usize,
+}
+
+impl InputRecorder {
+    pub fn new(path: &Path) -> std::io::Result<InputRecorder> {
+        Ok(InputRecorder {
+            file: File::create(path)?,
+        })
+    }
+
+    pub fn record(&mut self, keys_state: &[bool; 16]) -> std::io::Result<()> {
+        let mask = keys_state
+            .iter()
+            .enumerate()
+            .fold(0u16, |mask, (key, &is_pressed)| {
+                mask | ((is_pressed as u16) << key)
+            });
+        self.file.write_all(&mask.to_le_bytes())
+    }
+}
+
+impl InputReplay {
+    pub fn new(path: &Path) -> std::io::Result<InputReplay> {
+        let frames = std::fs::read(path)?
+            .chunks_exact(2)
+            .map(|mask| u16::from_le_bytes([mask[0], mask[1]]))
+            .collect();
+        Ok(InputReplay {
+            frames,
+            position: 0,
+        })
+    }
+
+    /// The keypad state of the next frame, `None` once the recording is over.
+    pub fn next_frame(&mut self) -> Option<[bool; 16]> {
+        let mask = self.frames.get(self.position)?;
+        self.position += 1;
+        Some(std::array::from_fn(|key| (mask >> key) & 1 == 1))
+    }
+}
@@ -1,9 +1,19 @@
-use crate::chip::init_chip8;
+use crate::chip::{init_chip8, print_stats};
 use crate::cli::parser::EmulatorConfig;
 use crate::devices::audio::AudioDevice;
 use crate::devices::display::DisplayDevice;
-use crate::devices::keyboard::KeyboardDevice;
+use crate::devices::keyboard::{Hotkey, KeyboardDevice};
+use crate::devices::overlay::DebugOverlay;
+use crate::devices::recording::{InputRecorder, InputReplay};
+use chip8::chip::Chip8;
 use chip8::display::Display;
+use chip8::platform::ChipMode;
+use std::path::Path;
+
+/// Emulation speed while the fast-forward key is held.
+const FAST_FORWARD_SPEED: f32 = 4.0;
+/// Emulation speed in slow motion.
+const SLOW_MOTION_SPEED: f32 = 0.5;
 
 mod chip;
 mod cli;
@@ -13,38 +23,148 @@ fn main() {
     let sdl_context = sdl2::init().unwrap();
 
     let config = EmulatorConfig::new();
-    let mut chip8 = init_chip8(
+    let mut chip8 = match init_chip8(
         &config.file,
         &config.mode,
         &config.quirks,
         config.ticks,
         config.sleep,
-    );
+        config.seed,
+        config.load_address,
+    ) {
+        Ok(chip8) => chip8,
+        Err(err) => {
+            eprintln!("Unable to load \"{}\": {err}", config.file);
+            std::process::exit(1);
+        }
+    };
+
+    let mut recorder = config.record.as_deref().map(|path| {
+        InputRecorder::new(path).unwrap_or_else(|err| {
+            eprintln!("Unable to record input to \"{}\": {err}", path.display());
+            std::process::exit(1);
+        })
+    });
+    let mut replay = config.replay.as_deref().map(|path| {
+        InputReplay::new(path).unwrap_or_else(|err| {
+            eprintln!("Unable to replay input from \"{}\": {err}", path.display());
+            std::process::exit(1);
+        })
+    });
 
-    let mut audio_device = AudioDevice::new(&sdl_context);
-    let mut keyboard_device = KeyboardDevice::new(&sdl_context);
+    let beep_hz = (config.mode != ChipMode::XOChip).then_some(config.beep_hz);
+    let mut audio_device = AudioDevice::new(&sdl_context, config.volume, config.waveform, beep_hz);
+    let mut keyboard_device = KeyboardDevice::new(&sdl_context, &config.keymap, config.hotkeys);
     let mut display_device = DisplayDevice::new(
         &sdl_context,
         "CHIP-8",
-        Display::HIRES_WIDTH as u32,
-        Display::HIRES_HEIGHT as u32,
+        Display::WIDTH as u32,
+        Display::HEIGHT as u32,
         config.scale as u32,
         config.palette,
+        config.fade,
     );
 
-    chip8.run(|keyboard, display, st_register_val, audio_buffer, pitch| {
-        display_device.draw(display);
-        audio_device.play_sound(st_register_val, audio_buffer, pitch);
-        keyboard_device
-            .keys_state()
+    chip8.set_frame_rate(Some(config.fps));
+    chip8.set_rewind_depth(config.rewind_frames);
+    let mut debug_overlay = DebugOverlay::new(config.debug_overlay);
+    let mut is_slow_motion = false;
+    // Rewinding pauses the machine, unless it was paused already.
+    let mut is_paused_by_rewind = false;
+
+    let result = chip8.run(|chip8| {
+        let is_paused = chip8.is_paused();
+        let overlay = debug_overlay.update(chip8);
+        display_device.draw(chip8.display(), overlay.as_deref());
+        let is_beeping = !is_paused && chip8.is_beeping();
+        audio_device.play_sound(is_beeping, chip8.audio_buffer(), chip8.pitch());
+
+        // Paused frames are neither recorded nor replayed, so the recording
+        // stays in sync with the executed frames.
+        let live_keys_state = keyboard_device.keys_state();
+        let keys_state = replay
+            .as_mut()
+            .filter(|_| !is_paused)
+            .and_then(|replay| replay.next_frame())
+            .unwrap_or(live_keys_state);
+        if let Some(Err(err)) = recorder
+            .as_mut()
+            .filter(|_| !is_paused)
+            .map(|recorder| recorder.record(&keys_state))
+        {
+            eprintln!("Unable to record input: {err}");
+        }
+        keys_state
             .iter()
             .enumerate()
             .for_each(|(key, &is_pressed)| {
                 if is_pressed {
-                    keyboard.press_key(key as u8);
+                    chip8.keyboard_mut().press_key(key as u8);
                 } else {
-                    keyboard.release_key(key as u8);
+                    chip8.keyboard_mut().release_key(key as u8);
                 }
             });
+
+        keyboard_device
+            .take_hotkeys()
+            .into_iter()
+            .for_each(|hotkey| match hotkey {
+                Hotkey::Screenshot => match display_device.screenshot(chip8.display()) {
+                    Ok(path) => println!("Screenshot saved to {}", path.display()),
+                    Err(err) => eprintln!("Unable to save screenshot: {err}"),
+                },
+                Hotkey::Pause if chip8.is_paused() => chip8.resume(),
+                Hotkey::Pause => chip8.pause(),
+                Hotkey::DebugOverlay => debug_overlay.toggle(),
+                Hotkey::SlowMotion => is_slow_motion = !is_slow_motion,
+            });
+
+        if keyboard_device.is_key_held(config.rewind_key) {
+            if !chip8.is_paused() {
+                chip8.pause();
+                is_paused_by_rewind = true;
+            }
+            chip8.step_back();
+        } else if is_paused_by_rewind {
+            chip8.resume();
+            is_paused_by_rewind = false;
+        }
+
+        // The speed changes the instructions per frame and the timer rate,
+        // the frame rate and so the sound stay the same.
+        let is_fast_forward = keyboard_device.is_key_held(config.fast_forward_key);
+        let speed = match (is_fast_forward, is_slow_motion) {
+            (true, _) => FAST_FORWARD_SPEED,
+            (false, true) => SLOW_MOTION_SPEED,
+            (false, false) => 1.0,
+        };
+        chip8.set_ticks_per_frame(((config.ticks as f32 * speed) as u32).max(1));
+        chip8.set_timer_speed(speed);
+        // Sleeping would slow the fast-forward down by as much.
+        chip8.set_sleep_time(if is_fast_forward { None } else { config.sleep });
+
+        if keyboard_device.quit_requested() {
+            on_exit(chip8, config.stats, config.dump_on_exit.as_deref());
+            std::process::exit(0);
+        }
     });
+
+    on_exit(&chip8, config.stats, config.dump_on_exit.as_deref());
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+/// Prints the statistics and dumps the memory if it was asked for.
+fn on_exit(chip8: &Chip8, stats: bool, dump_on_exit: Option<&Path>) {
+    if stats {
+        print_stats(chip8);
+    }
+    if let Some(path) = dump_on_exit {
+        match std::fs::write(path, chip8.dump_memory(..)) {
+            Ok(()) => println!("Memory dumped to {}", path.display()),
+            Err(err) => eprintln!("Unable to dump memory to \"{}\": {err}", path.display()),
+        }
+    }
 }